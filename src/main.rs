@@ -1,8 +1,10 @@
-use chrono::Local;
+use chrono::{Datelike, Days, Duration, Local, Months, NaiveDate, NaiveTime, TimeZone};
 use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ValueEnum, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -14,11 +16,45 @@ enum Priority {
 
 impl std::fmt::Display for Priority {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Priority::High => write!(f, "high"),
-            Priority::Medium => write!(f, "medium"),
-            Priority::Low => write!(f, "low"),
-        }
+        let name = match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        };
+        f.pad(name)
+    }
+}
+
+fn parse_priority(s: &str) -> Result<Priority, String> {
+    match s {
+        "1" => Ok(Priority::High),
+        "2" => Ok(Priority::Medium),
+        "3" => Ok(Priority::Low),
+        _ => Priority::from_str(s, true).map_err(|_| {
+            format!(
+                "invalid priority '{}': expected high, medium, low, or 1-3",
+                s
+            )
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Recurrence::Daily => "daily",
+            Recurrence::Weekly => "weekly",
+            Recurrence::Monthly => "monthly",
+        };
+        f.pad(name)
     }
 }
 
@@ -27,6 +63,74 @@ enum ListFilter {
     All,
     Done,
     Pending,
+    Overdue,
+}
+
+/// The todo(s) a `done` or `remove` command acts on.
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Id(u32),
+    /// Every todo matching the command's `--filter`
+    All,
+}
+
+fn parse_target(s: &str) -> Result<Target, String> {
+    if s == "all" {
+        return Ok(Target::All);
+    }
+    s.parse()
+        .map(Target::Id)
+        .map_err(|_| format!("invalid id '{}': expected a number or 'all'", s))
+}
+
+/// Rejects empty or whitespace-only titles, as `add` and the `edit`
+/// prompt do.
+fn parse_title(s: &str) -> Result<String, String> {
+    if s.trim().is_empty() {
+        return Err("title cannot be empty".to_string());
+    }
+    Ok(s.to_string())
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq)]
+enum OutputFormat {
+    Table,
+    Json,
+    /// Counts per priority and status of the filtered todos
+    CountTable,
+}
+
+#[derive(Debug, Clone, Default, ValueEnum, PartialEq)]
+enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq)]
+enum SortKey {
+    Id,
+    /// High, then medium, then low
+    Priority,
+    /// Earliest due first; todos without a due date last
+    Due,
+    /// Oldest first (also accepted as `age`)
+    #[value(alias = "age")]
+    Created,
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq)]
+enum InfoFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq)]
+enum ColumnLayout {
+    /// Fixed column widths
+    Fixed,
+    /// Size columns to fit their contents
+    Auto,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -37,17 +141,76 @@ struct Todo {
     priority: Priority,
     due_date: Option<String>,
     created_at: String,
+    #[serde(default)]
+    completed_at: Option<String>,
+    #[serde(default)]
+    assignee: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    escalated: bool,
+    /// Checkpoints as (label, YYYY-MM-DD date) pairs.
+    #[serde(default)]
+    milestones: Vec<(String, String)>,
+    /// Timestamped log lines, oldest first.
+    #[serde(default)]
+    comments: Vec<String>,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    note: Option<String>,
+    /// Never flag as due soon or overdue.
+    #[serde(default)]
+    silent: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
 struct TodoStore {
     next_id: u32,
     todos: Vec<Todo>,
+    /// Position reached by `list --next`.
+    #[serde(default)]
+    list_cursor: usize,
+    /// Consecutive days on which at least one todo was completed.
+    #[serde(default)]
+    completion_streak: u32,
+    #[serde(default)]
+    last_completion_date: Option<String>,
 }
 
 #[derive(Parser)]
 #[command(name = "todo-cli", about = "A simple CLI todo application")]
 struct Cli {
+    /// Use the global store in $HOME even inside a project with its own store
+    #[arg(long, global = true)]
+    global: bool,
+    /// Write the store as compact JSON instead of pretty-printed
+    #[arg(long, global = true)]
+    compact_store: bool,
+    /// Print the changes a command would make instead of saving them
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Fail instead of starting a new store when the store file is missing
+    #[arg(long, global = true)]
+    no_create: bool,
+    /// Bump pending todos older than this many days up one priority level
+    /// (each todo is bumped at most once)
+    #[arg(
+        long,
+        global = true,
+        value_name = "DAYS",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    escalate_after: Option<u32>,
+    /// Show at most this many rows from list and search
+    #[arg(long, global = true, value_name = "N")]
+    max_results: Option<usize>,
+    /// Done and pending markers for the table, e.g. "DONE,TODO"
+    #[arg(long, global = true, value_parser = parse_markers, value_name = "DONE,PENDING")]
+    markers: Option<TableStyle>,
+    /// Disable colored output (also off when NO_COLOR is set or stdout is not a terminal)
+    #[arg(long, global = true)]
+    no_color: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -56,53 +219,661 @@ struct Cli {
 enum Commands {
     /// Add a new todo
     Add {
-        /// Title of the todo
-        title: String,
-        /// Priority level
-        #[arg(long, value_enum, default_value_t = Priority::Medium)]
+        /// Title of the todo; pass several to add one todo per title
+        #[arg(required_unless_present = "interactive")]
+        titles: Vec<String>,
+        /// Priority level (high/medium/low or 1/2/3)
+        #[arg(long, value_parser = parse_priority, default_value = "medium")]
         priority: Priority,
         /// Due date in YYYY-MM-DD format
         #[arg(long)]
         due: Option<String>,
+        /// Person the todo is assigned to
+        #[arg(long)]
+        assignee: Option<String>,
+        /// Tag to attach (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Checkpoint as LABEL:YYYY-MM-DD (repeatable)
+        #[arg(long = "milestone", value_parser = parse_milestone, value_name = "LABEL:DATE")]
+        milestones: Vec<(String, String)>,
+        /// Prompt for the title, priority and due date
+        #[arg(long, conflicts_with_all = ["titles", "priority", "due"])]
+        interactive: bool,
+        /// Refuse to add a title that matches a pending todo (ignoring case)
+        #[arg(long)]
+        no_duplicates: bool,
+        /// Recreate the todo with the next due date each time it is completed
+        #[arg(long, value_enum, value_name = "INTERVAL")]
+        repeat: Option<Recurrence>,
+        /// Longer description shown by `show`
+        #[arg(long)]
+        note: Option<String>,
+        /// Never flag the todo as due soon or overdue
+        #[arg(long, alias = "no-due-reminder")]
+        silent: bool,
     },
     /// List todos
     List {
-        /// Filter todos
+        /// Filter todos [default: pending, or all with --query]
+        #[arg(long, value_enum)]
+        filter: Option<ListFilter>,
+        /// Only show todos whose title contains this text (case-insensitive)
+        #[arg(long)]
+        contains: Option<String>,
+        /// Only show todos assigned to this person (case-insensitive)
+        #[arg(long)]
+        assignee: Option<String>,
+        /// Only show todos carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only show todos whose title (ignoring case) appears more than once
+        #[arg(long)]
+        duplicates: bool,
+        /// Only show pending todos due between today and N days from now
+        #[arg(long, value_name = "DAYS")]
+        due_within: Option<u32>,
+        /// Only show pending todos overdue by at least N days
+        #[arg(long, value_name = "DAYS")]
+        overdue_days: Option<i64>,
+        /// Only show todos created within a relative window, e.g. 7d or 2w
+        #[arg(long, value_parser = parse_relative_days, value_name = "AGE")]
+        since: Option<i64>,
+        /// Filter expression, e.g. "priority:high AND NOT done"
+        #[arg(long)]
+        query: Option<String>,
+        /// Sort order
+        #[arg(long, value_enum, default_value_t = SortKey::Id)]
+        sort: SortKey,
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+        /// Sort by creation date, most recent first (same as --sort created --reverse)
+        #[arg(long, conflicts_with_all = ["oldest", "sort", "reverse"])]
+        newest: bool,
+        /// Sort by creation date, oldest first (same as --sort created)
+        #[arg(long, conflicts_with_all = ["sort", "reverse"])]
+        oldest: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Show only the first N todos after filtering and sorting (use
+        /// --page-size when paging with --next)
+        #[arg(long, value_name = "N", conflicts_with = "next")]
+        limit: Option<usize>,
+        /// With --format json, print a lone result as an object, not an array
+        #[arg(long)]
+        unwrap_single: bool,
+        /// Table column layout
+        #[arg(long, value_enum, default_value_t = ColumnLayout::Fixed)]
+        columns: ColumnLayout,
+        /// Show the next page after the one shown by the previous --next
+        #[arg(long, conflicts_with = "format")]
+        next: bool,
+        /// Number of todos per page for --next
+        #[arg(
+            long,
+            default_value_t = 10,
+            requires = "next",
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        page_size: usize,
+        /// Start --next paging from the first todo again
+        #[arg(long)]
+        reset_cursor: bool,
+        /// Add a column with each todo's completion date (useful with
+        /// --filter all or done)
+        #[arg(long)]
+        show_completed_at: bool,
+        /// Add a column with each todo's next upcoming milestone
+        #[arg(long)]
+        milestones: bool,
+        /// Add extra detail columns such as the assignee
+        #[arg(long)]
+        wide: bool,
+    },
+    /// List pending todos whose due date has passed
+    Overdue,
+    /// Show every detail of one todo
+    Show {
+        /// ID of the todo to show
+        id: u32,
+    },
+    /// Find todos whose title contains some text
+    Search {
+        /// Text to look for in titles
+        query: String,
+        /// Match case exactly
+        #[arg(long)]
+        case_sensitive: bool,
+    },
+    /// Mark one or more todos as completed
+    Done {
+        /// IDs of the todos to complete, or `all` for every todo matching --filter
+        #[arg(required = true, value_parser = parse_target)]
+        targets: Vec<Target>,
+        /// Which todos `all` expands to
         #[arg(long, value_enum, default_value_t = ListFilter::Pending)]
         filter: ListFilter,
     },
-    /// Mark a todo as completed
-    Done {
-        /// ID of the todo to complete
+    /// Mark a completed todo as pending again
+    Undone {
+        /// ID of the todo to reopen
         id: u32,
     },
-    /// Remove a todo
-    Remove {
-        /// ID of the todo to remove
+    /// Append a timestamped comment to a todo
+    Comment {
+        /// ID of the todo to comment on
+        id: u32,
+        /// Comment text
+        text: String,
+    },
+    /// Edit an existing todo's title, priority, due date, or assignee
+    Edit {
+        /// ID of the todo to edit
         id: u32,
+        /// New title
+        #[arg(long, value_parser = parse_title)]
+        title: Option<String>,
+        /// New priority (high/medium/low or 1/2/3)
+        #[arg(long, value_parser = parse_priority)]
+        priority: Option<Priority>,
+        /// New due date in YYYY-MM-DD format; pass "" to clear it
+        #[arg(long)]
+        due: Option<String>,
+        /// New assignee; pass "" to clear it
+        #[arg(long)]
+        assignee: Option<String>,
+    },
+    /// Remove one or more todos
+    Remove {
+        /// IDs of the todos to remove, or `all` for every todo matching --filter
+        #[arg(
+            value_parser = parse_target,
+            required_unless_present = "before",
+            conflicts_with = "before"
+        )]
+        targets: Vec<Target>,
+        /// Which todos `all` expands to
+        #[arg(long, value_enum, default_value_t = ListFilter::Pending)]
+        filter: ListFilter,
+        /// Remove every todo created before this date (YYYY-MM-DD)
+        #[arg(long)]
+        before: Option<String>,
+        /// Skip the confirmation prompt; required for `remove all`
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Remove every completed todo
+    ClearDone {
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Revert the last change to the store (run again to redo it)
+    Undo,
+    /// Reassign ids as 1, 2, 3, ... in current order
+    Renumber {
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Merge todos from a JSON file made by `export --format json`
+    Import {
+        /// File to read
+        path: PathBuf,
+        /// Give every imported todo a fresh id; with `false`, ids are kept
+        /// unless they collide
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        reassign_ids: bool,
+        /// Replace the whole store with the file, e.g. one written by
+        /// `export --format json --include-all`
+        #[arg(long, conflicts_with = "reassign_ids")]
+        replace: bool,
+    },
+    /// Write every todo as CSV or JSON
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// File to write instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// With --format json, write the whole store (todos and metadata such
+        /// as the list cursor and streak) as one object
+        #[arg(long)]
+        include_all: bool,
+    },
+    /// Show which store file is in use
+    Where {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = InfoFormat::Text)]
+        format: InfoFormat,
+    },
+    /// Print a JSON Schema describing the store file
+    Schema,
+    /// Show how many consecutive days you have completed todos
+    Streak,
+    /// Summarize todo counts by status and priority
+    Stats {
+        /// Also show this month's completions as a calendar grid
+        #[arg(long)]
+        streak_calendar: bool,
+    },
+    /// Summarize completed todos by day
+    Digest {
+        /// Cover the current week (Monday to Sunday)
+        #[arg(long, required = true)]
+        week: bool,
     },
 }
 
-fn store_path() -> PathBuf {
-    let home = std::env::var("HOME").expect("HOME environment variable not set");
-    PathBuf::from(home).join(".todo-cli.json")
+const STORE_FILE_NAME: &str = ".todo-cli.json";
+
+/// Errors from locating, reading or writing the store file, plus `Input`
+/// for bad command-line input such as an unknown id or a malformed date,
+/// and `Prompt` for failed terminal I/O while asking a question.
+#[derive(Debug)]
+enum StoreError {
+    NoHome,
+    Missing(PathBuf),
+    Read(PathBuf, io::Error),
+    NotUtf8,
+    Parse(PathBuf, serde_json::Error),
+    Write(PathBuf, io::Error),
+    Input(String),
+    Prompt(io::Error),
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StoreError::NoHome => write!(f, "HOME environment variable not set"),
+            StoreError::Missing(path) => {
+                write!(f, "store file {} does not exist", path.display())
+            }
+            StoreError::Read(path, e) => {
+                write!(f, "cannot read store file {}: {}", path.display(), e)
+            }
+            StoreError::NotUtf8 => write!(f, "store file is not valid UTF-8"),
+            StoreError::Parse(path, e) => {
+                write!(f, "store file {} is not valid JSON: {}", path.display(), e)
+            }
+            StoreError::Write(path, e) => {
+                write!(f, "cannot write store file {}: {}", path.display(), e)
+            }
+            StoreError::Input(message) => write!(f, "{}", message),
+            StoreError::Prompt(e) => write!(f, "cannot read input: {}", e),
+        }
+    }
+}
+
+impl StoreError {
+    /// Status `main` exits with. A missing store under `--no-create` and a
+    /// non-UTF-8 store keep their original status 4.
+    fn exit_code(&self) -> i32 {
+        match self {
+            StoreError::Missing(_) | StoreError::NotUtf8 => 4,
+            StoreError::NoHome
+            | StoreError::Read(..)
+            | StoreError::Parse(..)
+            | StoreError::Write(..)
+            | StoreError::Input(_)
+            | StoreError::Prompt(_) => 1,
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// Error for ids that match no todo.
+fn not_found(ids: &[u32]) -> StoreError {
+    let ids: Vec<String> = ids.iter().map(|id| format!("#{}", id)).collect();
+    StoreError::Input(format!("Todo {} not found.", ids.join(", ")))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Looks for a store file in `start` and each parent directory up to
+/// `stop` (normally `$HOME`). When `start` is not inside `stop`, only
+/// `start` itself is checked, so unrelated stores under e.g. /tmp are
+/// never picked up.
+fn find_store_path(start: &Path, stop: Option<&Path>) -> Option<PathBuf> {
+    let stop = stop.filter(|stop| start.starts_with(stop)).unwrap_or(start);
+    for dir in start.ancestors() {
+        let candidate = dir.join(STORE_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir == stop {
+            break;
+        }
+    }
+    None
+}
+
+/// Resolves the store file: `--global` forces `$HOME/.todo-cli.json`,
+/// otherwise a non-empty `TODO_CLI_STORE`, then the nearest project
+/// store, then the global file.
+fn store_path(global: bool) -> Result<PathBuf, StoreError> {
+    resolve_store_path(
+        global,
+        std::env::var("TODO_CLI_STORE").ok().as_deref(),
+        std::env::current_dir().ok().as_deref(),
+        home_dir().as_deref(),
+    )
+}
+
+/// `store_path` with the environment passed in.
+fn resolve_store_path(
+    global: bool,
+    env_store: Option<&str>,
+    cwd: Option<&Path>,
+    home: Option<&Path>,
+) -> Result<PathBuf, StoreError> {
+    let home_store = || {
+        home.map(|home| home.join(STORE_FILE_NAME))
+            .ok_or(StoreError::NoHome)
+    };
+    if global {
+        return home_store();
+    }
+    if let Some(path) = env_store.filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    match cwd.and_then(|dir| find_store_path(dir, home)) {
+        Some(path) => Ok(path),
+        None => home_store(),
+    }
+}
+
+fn read_store_file(path: &Path) -> Result<String, StoreError> {
+    fs::read_to_string(path).map_err(|e| {
+        if e.kind() == io::ErrorKind::InvalidData {
+            StoreError::NotUtf8
+        } else {
+            StoreError::Read(path.to_path_buf(), e)
+        }
+    })
 }
 
-fn load_store(path: &Path) -> TodoStore {
+fn require_store(path: &Path) -> Result<(), StoreError> {
     if path.exists() {
-        let data = fs::read_to_string(path).expect("Failed to read store file");
-        serde_json::from_str(&data).unwrap_or_default()
+        Ok(())
     } else {
-        TodoStore {
+        Err(StoreError::Missing(path.to_path_buf()))
+    }
+}
+
+fn load_store(path: &Path) -> Result<TodoStore, StoreError> {
+    if !path.exists() {
+        return Ok(TodoStore {
             next_id: 1,
             ..Default::default()
+        });
+    }
+    let data = read_store_file(path)?;
+    serde_json::from_str(&data).map_err(|e| StoreError::Parse(path.to_path_buf(), e))
+}
+
+/// Loads the store for a command; with `no_create` a missing file is an
+/// error instead of a fresh store.
+fn open_store(path: &Path, no_create: bool) -> Result<TodoStore, StoreError> {
+    if no_create {
+        require_store(path)?;
+    }
+    load_store(path)
+}
+
+fn save_store(store: &TodoStore, path: &Path, compact: bool) -> Result<(), StoreError> {
+    write_store(store, path, compact, true)
+}
+
+fn write_store(
+    store: &TodoStore,
+    path: &Path,
+    compact: bool,
+    backup: bool,
+) -> Result<(), StoreError> {
+    let data = if compact {
+        serde_json::to_string(store)
+    } else {
+        serde_json::to_string_pretty(store)
+    }
+    .expect("Failed to serialize store");
+    // Write beside the store and rename over it so an interrupted save
+    // never leaves a truncated file behind. With `backup`, the old
+    // contents are kept for `undo`.
+    let tmp = temp_store_path(path);
+    let result = fs::write(&tmp, data)
+        .and_then(|()| {
+            if backup && path.is_file() {
+                fs::copy(path, backup_store_path(path))?;
+            }
+            Ok(())
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        fs::remove_file(&tmp).ok();
+    }
+    result.map_err(|e| StoreError::Write(path.to_path_buf(), e))
+}
+
+fn temp_store_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// `.todo-cli.json` is backed up as `.todo-cli.bak.json`.
+fn backup_store_path(path: &Path) -> PathBuf {
+    path.with_extension("bak.json")
+}
+
+/// Swaps the backup written by the last save back into place. Saving
+/// moves the current contents into the backup, so a second undo redoes.
+/// Returns false when there is no backup.
+fn restore_backup(path: &Path, compact: bool, dry_run: bool) -> Result<bool, StoreError> {
+    let backup = backup_store_path(path);
+    if !backup.exists() {
+        return Ok(false);
+    }
+    let previous = load_store(&backup)?;
+    persist(&previous, path, compact, dry_run)?;
+    Ok(true)
+}
+
+/// Describes how `after` differs from `before`, one line per added, updated
+/// or removed todo and per changed store field such as `list_cursor`.
+/// Updates list each changed field as `old -> new`.
+fn plan_changes(before: &TodoStore, after: &TodoStore) -> Vec<String> {
+    let mut plan = Vec::new();
+    for todo in &after.todos {
+        match before.todos.iter().find(|t| t.id == todo.id) {
+            None => plan.push(format!("+ add #{} {:?}", todo.id, todo.title)),
+            Some(old) if old != todo => {
+                let old_fields = serde_json::to_value(old).expect("Failed to serialize todo");
+                let new_fields = serde_json::to_value(todo).expect("Failed to serialize todo");
+                let changes: Vec<String> = new_fields
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter(|(key, value)| old_fields.get(key.as_str()) != Some(value))
+                    .map(|(key, value)| {
+                        let old_value = old_fields
+                            .get(key.as_str())
+                            .unwrap_or(&serde_json::Value::Null);
+                        format!("{}: {} -> {}", key, old_value, value)
+                    })
+                    .collect();
+                plan.push(format!(
+                    "~ update #{} {:?}: {}",
+                    todo.id,
+                    old.title,
+                    changes.join(", ")
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for todo in &before.todos {
+        if !after.todos.iter().any(|t| t.id == todo.id) {
+            plan.push(format!("- remove #{} {:?}", todo.id, todo.title));
+        }
+    }
+    let old_fields = serde_json::to_value(before).expect("Failed to serialize store");
+    let new_fields = serde_json::to_value(after).expect("Failed to serialize store");
+    for (key, value) in new_fields.as_object().into_iter().flatten() {
+        let old_value = old_fields
+            .get(key.as_str())
+            .unwrap_or(&serde_json::Value::Null);
+        if key != "todos" && old_value != value {
+            plan.push(format!("~ set {}: {} -> {}", key, old_value, value));
+        }
+    }
+    plan
+}
+
+/// Saves the store, or under `--dry-run` prints the planned changes
+/// against what is currently on disk and leaves the file untouched.
+/// Returns whether anything was saved, so callers only report success
+/// for real changes.
+fn persist(
+    store: &TodoStore,
+    path: &Path,
+    compact: bool,
+    dry_run: bool,
+) -> Result<bool, StoreError> {
+    if dry_run {
+        print_dry_run(store, path)?;
+        return Ok(false);
+    }
+    save_store(store, path, compact)?;
+    Ok(true)
+}
+
+/// Like `persist`, but keeps the existing undo backup. Used for
+/// bookkeeping saves so they do not use up the single undo level.
+fn persist_without_backup(
+    store: &TodoStore,
+    path: &Path,
+    compact: bool,
+    dry_run: bool,
+) -> Result<bool, StoreError> {
+    if dry_run {
+        print_dry_run(store, path)?;
+        return Ok(false);
+    }
+    write_store(store, path, compact, false)?;
+    Ok(true)
+}
+
+/// Saves `cursor` as the `list --next` position, leaving everything else as
+/// it is on disk. Changes made only in memory, such as `--escalate-after`
+/// bumps, stay out of this backup-less save.
+fn save_list_cursor(
+    path: &Path,
+    cursor: usize,
+    compact: bool,
+    dry_run: bool,
+) -> Result<bool, StoreError> {
+    let mut store = load_store(path)?;
+    store.list_cursor = cursor;
+    persist_without_backup(&store, path, compact, dry_run)
+}
+
+fn print_dry_run(store: &TodoStore, path: &Path) -> Result<(), StoreError> {
+    let plan = plan_changes(&load_store(path)?, store);
+    if plan.is_empty() {
+        println!("Dry run: no changes.");
+    } else {
+        println!("Dry run: nothing saved. Planned changes:");
+        for line in plan {
+            println!("  {}", line);
         }
     }
+    Ok(())
 }
 
-fn save_store(store: &TodoStore, path: &Path) {
-    let data = serde_json::to_string_pretty(store).expect("Failed to serialize store");
-    fs::write(path, data).expect("Failed to write store file");
+#[derive(Debug, Serialize, PartialEq)]
+struct StoreInfo {
+    path: String,
+    exists: bool,
+    count: usize,
+}
+
+fn store_info(path: &Path) -> Result<StoreInfo, StoreError> {
+    let exists = path.exists();
+    Ok(StoreInfo {
+        path: path.display().to_string(),
+        exists,
+        count: if exists {
+            load_store(path)?.todos.len()
+        } else {
+            0
+        },
+    })
+}
+
+/// Hand-written JSON Schema for the store file. Keep in sync with `Todo`
+/// and `TodoStore`.
+fn store_schema() -> serde_json::Value {
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "TodoStore",
+        "type": "object",
+        "required": ["next_id", "todos"],
+        "properties": {
+            "next_id": { "type": "integer", "minimum": 0 },
+            "todos": { "type": "array", "items": { "$ref": "#/$defs/Todo" } },
+            "list_cursor": { "type": "integer", "minimum": 0 },
+            "completion_streak": { "type": "integer", "minimum": 0 },
+            "last_completion_date": { "type": ["string", "null"], "format": "date" }
+        },
+        "$defs": {
+            "Todo": {
+                "type": "object",
+                "required": ["id", "title", "completed", "priority", "created_at"],
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0 },
+                    "title": { "type": "string" },
+                    "completed": { "type": "boolean" },
+                    "priority": { "enum": ["high", "medium", "low"] },
+                    "due_date": { "type": ["string", "null"], "format": "date" },
+                    "created_at": { "type": "string", "format": "date" },
+                    "completed_at": { "type": ["string", "null"], "format": "date" },
+                    "assignee": { "type": ["string", "null"] },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "escalated": { "type": "boolean" },
+                    "milestones": {
+                        "type": "array",
+                        "items": {
+                            "type": "array",
+                            "prefixItems": [
+                                { "type": "string" },
+                                { "type": "string", "format": "date" }
+                            ],
+                            "minItems": 2,
+                            "maxItems": 2
+                        }
+                    },
+                    "comments": { "type": "array", "items": { "type": "string" } },
+                    "recurrence": { "enum": ["daily", "weekly", "monthly", null] },
+                    "note": { "type": ["string", "null"] },
+                    "silent": { "type": "boolean" }
+                }
+            }
+        }
+    })
+}
+
+/// Validates a `YYYY-MM-DD` due date and returns it zero-padded.
+fn parse_due_date(s: &str) -> Result<String, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|e| format!("Invalid due date '{}': {} (expected YYYY-MM-DD).", s, e))
 }
 
 fn add_todo(store: &mut TodoStore, title: String, priority: Priority, due: Option<String>) -> u32 {
@@ -115,273 +886,4912 @@ fn add_todo(store: &mut TodoStore, title: String, priority: Priority, due: Optio
         priority,
         due_date: due,
         created_at: Local::now().format("%Y-%m-%d").to_string(),
+        completed_at: None,
+        assignee: None,
+        tags: Vec::new(),
+        escalated: false,
+        milestones: Vec::new(),
+        comments: Vec::new(),
+        recurrence: None,
+        note: None,
+        silent: false,
     };
     store.todos.push(todo);
     id
 }
 
-fn mark_done(store: &mut TodoStore, id: u32) -> bool {
-    if let Some(todo) = store.todos.iter_mut().find(|t| t.id == id) {
-        todo.completed = true;
-        true
-    } else {
-        false
+fn add_todos(
+    store: &mut TodoStore,
+    titles: &[String],
+    priority: Priority,
+    due: Option<String>,
+) -> Vec<u32> {
+    titles
+        .iter()
+        .map(|title| add_todo(store, title.clone(), priority.clone(), due.clone()))
+        .collect()
+}
+
+/// Raises the priority of pending todos created more than `days` days
+/// before `today` by one level. Each todo is escalated at most once.
+fn escalate_stale(store: &mut TodoStore, today: NaiveDate, days: u32) -> usize {
+    let mut count = 0;
+    for todo in store.todos.iter_mut() {
+        if todo.completed || todo.escalated || todo.priority == Priority::High {
+            continue;
+        }
+        let Ok(created) = NaiveDate::parse_from_str(&todo.created_at, "%Y-%m-%d") else {
+            continue;
+        };
+        if today - created > Duration::days(days.into()) {
+            todo.priority = match todo.priority {
+                Priority::Low => Priority::Medium,
+                _ => Priority::High,
+            };
+            todo.escalated = true;
+            count += 1;
+        }
     }
+    count
 }
 
-fn remove_todo(store: &mut TodoStore, id: u32) -> bool {
-    let len_before = store.todos.len();
-    store.todos.retain(|t| t.id != id);
-    store.todos.len() < len_before
+fn parse_milestone(s: &str) -> Result<(String, String), String> {
+    let (label, date) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("invalid milestone '{}': expected LABEL:YYYY-MM-DD", s))?;
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(format!("invalid milestone '{}': label is empty", s));
+    }
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("invalid milestone date in '{}': expected YYYY-MM-DD", s))?;
+    Ok((label.to_string(), date.format("%Y-%m-%d").to_string()))
 }
 
-fn filter_todos<'a>(store: &'a TodoStore, filter: &ListFilter) -> Vec<&'a Todo> {
-    store
-        .todos
+/// The earliest milestone falling on or after `today`.
+fn next_milestone(todo: &Todo, today: NaiveDate) -> Option<&(String, String)> {
+    todo.milestones
         .iter()
-        .filter(|t| match filter {
-            ListFilter::All => true,
-            ListFilter::Done => t.completed,
-            ListFilter::Pending => !t.completed,
+        .filter_map(|m| {
+            NaiveDate::parse_from_str(&m.1, "%Y-%m-%d")
+                .ok()
+                .filter(|d| *d >= today)
+                .map(|d| (d, m))
         })
-        .collect()
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, m)| m)
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let path = store_path();
+fn find_todo_mut(store: &mut TodoStore, id: u32) -> Option<&mut Todo> {
+    store.todos.iter_mut().find(|t| t.id == id)
+}
 
-    match cli.command {
-        Commands::Add {
-            title,
-            priority,
-            due,
-        } => {
-            let mut store = load_store(&path);
-            let id = add_todo(&mut store, title.clone(), priority, due);
-            save_store(&store, &path);
-            println!("Added todo #{}: {}", id, title);
+fn mark_done(store: &mut TodoStore, id: u32) -> bool {
+    if let Some(todo) = store.todos.iter_mut().find(|t| t.id == id) {
+        if !todo.completed {
+            todo.completed_at = Some(Local::now().format("%Y-%m-%d").to_string());
         }
-        Commands::List { filter } => {
-            let store = load_store(&path);
-            let todos = filter_todos(&store, &filter);
+        todo.completed = true;
+        true
+    } else {
+        false
+    }
+}
 
-            if todos.is_empty() {
-                println!("No todos found.");
-                return;
-            }
+fn mark_undone(store: &mut TodoStore, id: u32) -> bool {
+    if let Some(todo) = store.todos.iter_mut().find(|t| t.id == id) {
+        todo.completed = false;
+        todo.completed_at = None;
+        true
+    } else {
+        false
+    }
+}
 
-            println!(
-                "{:<5} {:<6} {:<8} {:<12} Title",
-                "ID", "Done", "Priority", "Due"
-            );
-            println!("{}", "-".repeat(60));
-            for t in todos {
-                let done = if t.completed { "[x]" } else { "[ ]" };
-                let due = t.due_date.as_deref().unwrap_or("-");
-                println!(
-                    "{:<5} {:<6} {:<8} {:<12} {}",
-                    t.id, done, t.priority, due, t.title
-                );
-            }
-        }
-        Commands::Done { id } => {
-            let mut store = load_store(&path);
-            if mark_done(&mut store, id) {
-                save_store(&store, &path);
-                println!("Marked todo #{} as done.", id);
-            } else {
-                eprintln!("Todo #{} not found.", id);
-                std::process::exit(1);
-            }
-        }
-        Commands::Remove { id } => {
-            let mut store = load_store(&path);
-            if remove_todo(&mut store, id) {
-                save_store(&store, &path);
-                println!("Removed todo #{}.", id);
-            } else {
-                eprintln!("Todo #{} not found.", id);
-                std::process::exit(1);
-            }
+/// Appends `text` to a todo's comments, prefixed with `stamp`.
+fn add_comment(store: &mut TodoStore, id: u32, text: &str, stamp: &str) -> bool {
+    match find_todo_mut(store, id) {
+        Some(todo) => {
+            todo.comments.push(format!("{} {}", stamp, text));
+            true
         }
+        None => false,
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
+/// Steps `date` forward by one interval. Monthly steps clamp to the end of
+/// a shorter month, so Jan 31 is followed by the last day of February.
+/// Returns `None` past 9999-12-31, the last date `parse_due_date` reads.
+fn advance_date(date: NaiveDate, recurrence: Recurrence) -> Option<NaiveDate> {
+    match recurrence {
+        Recurrence::Daily => date.checked_add_signed(Duration::days(1)),
+        Recurrence::Weekly => date.checked_add_signed(Duration::weeks(1)),
+        Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+    }
+    .filter(|next| next.year() <= 9999)
+}
 
-    fn empty_store() -> TodoStore {
-        TodoStore {
-            next_id: 1,
-            todos: Vec::new(),
-        }
+/// Adds the next occurrence of a recurring todo, due one interval after
+/// its current due date (or `today` if it has none). Returns the new id,
+/// `None` for a one-off todo, or an error when the next date is out of
+/// range.
+fn schedule_next(store: &mut TodoStore, id: u32, today: NaiveDate) -> Result<Option<u32>, String> {
+    let Some(todo) = store.todos.iter().find(|t| t.id == id) else {
+        return Ok(None);
+    };
+    let Some(recurrence) = todo.recurrence else {
+        return Ok(None);
+    };
+    let due = advance_date(parsed_due(todo).unwrap_or(today), recurrence).ok_or_else(|| {
+        format!(
+            "The next {} occurrence of todo #{} would fall after 9999-12-31; not scheduling it.",
+            recurrence, id
+        )
+    })?;
+    let (title, priority) = (todo.title.clone(), todo.priority.clone());
+    let (assignee, tags) = (todo.assignee.clone(), todo.tags.clone());
+    let (note, silent) = (todo.note.clone(), todo.silent);
+    let next_id = add_todo(
+        store,
+        title,
+        priority,
+        Some(due.format("%Y-%m-%d").to_string()),
+    );
+    if let Some(next) = find_todo_mut(store, next_id) {
+        next.assignee = assignee;
+        next.tags = tags;
+        next.note = note;
+        next.silent = silent;
+        next.recurrence = Some(recurrence);
     }
+    Ok(Some(next_id))
+}
 
-    fn temp_path() -> PathBuf {
-        let mut path = std::env::temp_dir();
-        path.push(format!("todo-cli-test-{}.json", std::process::id()));
-        path
+/// Returns the streak after completing a todo on `today`, given the streak
+/// and date of the previous completion.
+fn update_streak(streak: u32, last: Option<NaiveDate>, today: NaiveDate) -> u32 {
+    match last {
+        Some(last) if last == today => streak.max(1),
+        Some(last) if last + Duration::days(1) == today => streak + 1,
+        _ => 1,
     }
+}
 
-    // -- add_todo tests --
+/// The streak as of `today`: it lapses once a whole day passes without a
+/// completion.
+fn active_streak(streak: u32, last: Option<NaiveDate>, today: NaiveDate) -> u32 {
+    match last {
+        Some(last) if today - last <= Duration::days(1) => streak,
+        _ => 0,
+    }
+}
 
-    #[test]
-    fn add_todo_assigns_incrementing_ids() {
-        let mut store = empty_store();
-        let id1 = add_todo(&mut store, "First".into(), Priority::Low, None);
-        let id2 = add_todo(&mut store, "Second".into(), Priority::High, None);
-        assert_eq!(id1, 1);
+fn record_completion(store: &mut TodoStore, today: NaiveDate) {
+    let last = store
+        .last_completion_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    store.completion_streak = update_streak(store.completion_streak, last, today);
+    store.last_completion_date = Some(today.format("%Y-%m-%d").to_string());
+}
+
+/// Applies only the provided fields. An empty `due` clears the due date.
+fn edit_todo(
+    store: &mut TodoStore,
+    id: u32,
+    title: Option<String>,
+    priority: Option<Priority>,
+    due: Option<String>,
+) -> bool {
+    let Some(todo) = find_todo_mut(store, id) else {
+        return false;
+    };
+    if let Some(title) = title {
+        todo.title = title;
+    }
+    if let Some(priority) = priority {
+        todo.priority = priority;
+    }
+    if let Some(due) = due {
+        todo.due_date = if due.is_empty() { None } else { Some(due) };
+    }
+    true
+}
+
+/// Sets one named field of `todo` from text, as entered at the `edit`
+/// prompt. An empty value clears `due` and `assignee`.
+fn apply_field_edit(todo: &mut Todo, field: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match field.trim().to_lowercase().as_str() {
+        "title" if value.is_empty() => return Err("Title cannot be empty.".to_string()),
+        "title" => todo.title = value.to_string(),
+        "priority" => todo.priority = parse_priority(value)?,
+        "due" if value.is_empty() => todo.due_date = None,
+        "due" => todo.due_date = Some(parse_due_date(value)?),
+        "assignee" if value.is_empty() => todo.assignee = None,
+        "assignee" => todo.assignee = Some(value.to_string()),
+        other => {
+            return Err(format!(
+                "unknown field '{}': expected title, priority, due, or assignee",
+                other
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Monday and Sunday of the week containing `day`.
+fn week_bounds(day: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = day - Duration::days(day.weekday().num_days_from_monday().into());
+    (start, start + Duration::days(6))
+}
+
+/// Groups todos completed between `start` and `end` (inclusive) by
+/// completion day, oldest day first.
+fn completions_by_day_between(
+    store: &TodoStore,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<(NaiveDate, Vec<&Todo>)> {
+    let mut days: Vec<(NaiveDate, Vec<&Todo>)> = Vec::new();
+    for todo in store.todos.iter().filter(|t| t.completed) {
+        let Some(day) = todo
+            .completed_at
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        if day < start || day > end {
+            continue;
+        }
+        match days.iter_mut().find(|(d, _)| *d == day) {
+            Some((_, todos)) => todos.push(todo),
+            None => days.push((day, vec![todo])),
+        }
+    }
+    days.sort_by_key(|(d, _)| *d);
+    days
+}
+
+/// First and last day of the month containing `day`.
+fn month_bounds(day: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = day.with_day(1).expect("every month has a first day");
+    let end = start
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(NaiveDate::MAX);
+    (start, end)
+}
+
+/// Number of todos completed on each day from `start` to `end`
+/// (inclusive), including days with none.
+fn completions_by_day(
+    store: &TodoStore,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<(NaiveDate, usize)> {
+    let done = completions_by_day_between(store, start, end);
+    start
+        .iter_days()
+        .take_while(|day| *day <= end)
+        .map(|day| {
+            let count = done
+                .iter()
+                .find(|(d, _)| *d == day)
+                .map_or(0, |(_, todos)| todos.len());
+            (day, count)
+        })
+        .collect()
+}
+
+const CALENDAR_SHADES: [char; 5] = ['.', '░', '▒', '▓', '█'];
+
+/// Shade for a day with `count` completions: none, 1, 2, 3-4, or 5+.
+fn intensity(count: usize) -> char {
+    let level = match count {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 | 4 => 3,
+        _ => 4,
+    };
+    CALENDAR_SHADES[level]
+}
+
+/// GitHub-style grid of `days`: one row per weekday, one column per week,
+/// headed by the first day's month.
+fn render_streak_calendar(days: &[(NaiveDate, usize)]) -> String {
+    let Some(&(first, _)) = days.first() else {
+        return String::new();
+    };
+    let offset = first.weekday().num_days_from_monday() as usize;
+    let weeks = (offset + days.len()).div_ceil(7);
+    let mut grid = vec![vec![' '; weeks]; 7];
+    for (i, (_, count)) in days.iter().enumerate() {
+        let slot = offset + i;
+        grid[slot % 7][slot / 7] = intensity(*count);
+    }
+    let mut lines = vec![first.format("%B %Y").to_string()];
+    let weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    for (name, row) in weekdays.iter().zip(grid) {
+        let cells: Vec<String> = row.iter().map(char::to_string).collect();
+        lines.push(
+            format!("{} {}", name, cells.join(" "))
+                .trim_end()
+                .to_string(),
+        );
+    }
+    let shades: Vec<String> = CALENDAR_SHADES.iter().map(char::to_string).collect();
+    lines.push(format!("Less {} More", shades.join(" ")));
+    lines.join("\n")
+}
+
+fn remove_todo(store: &mut TodoStore, id: u32) -> bool {
+    let len_before = store.todos.len();
+    store.todos.retain(|t| t.id != id);
+    store.todos.len() < len_before
+}
+
+fn created_before(todo: &Todo, cutoff: NaiveDate) -> bool {
+    NaiveDate::parse_from_str(&todo.created_at, "%Y-%m-%d").is_ok_and(|created| created < cutoff)
+}
+
+fn remove_created_before(store: &mut TodoStore, cutoff: NaiveDate) -> usize {
+    let len_before = store.todos.len();
+    store.todos.retain(|t| !created_before(t, cutoff));
+    len_before - store.todos.len()
+}
+
+/// Removes completed todos and returns how many were deleted. `next_id`
+/// is left alone so ids are never reused.
+fn clear_done(store: &mut TodoStore) -> usize {
+    let len_before = store.todos.len();
+    store.todos.retain(|t| !t.completed);
+    len_before - store.todos.len()
+}
+
+/// Gives todos sequential ids from 1 in their current order and resets
+/// `next_id` to follow the last one. Returns the (old, new) id of every
+/// todo whose id changed.
+fn renumber(store: &mut TodoStore) -> Vec<(u32, u32)> {
+    let mut changed = Vec::new();
+    for (todo, id) in store.todos.iter_mut().zip(1..) {
+        if todo.id != id {
+            changed.push((todo.id, id));
+        }
+        todo.id = id;
+    }
+    store.next_id = store.todos.len() as u32 + 1;
+    changed
+}
+
+/// The filter `list` applies: the one given, otherwise pending todos, or
+/// every todo when a `--query` does the filtering.
+fn list_filter(filter: Option<ListFilter>, has_query: bool) -> ListFilter {
+    match filter {
+        Some(filter) => filter,
+        None if has_query => ListFilter::All,
+        None => ListFilter::Pending,
+    }
+}
+
+fn filter_todos<'a>(store: &'a TodoStore, filter: &ListFilter) -> Vec<&'a Todo> {
+    let today = Local::now().date_naive();
+    store
+        .todos
+        .iter()
+        .filter(|t| match filter {
+            ListFilter::All => true,
+            ListFilter::Done => t.completed,
+            ListFilter::Pending => !t.completed,
+            ListFilter::Overdue => is_overdue(t, today),
+        })
+        .collect()
+}
+
+/// Expands command targets into the ids they refer to. `all` may not be
+/// mixed with explicit ids.
+fn expand_targets(
+    store: &TodoStore,
+    targets: &[Target],
+    filter: &ListFilter,
+) -> Result<Vec<u32>, String> {
+    if targets.len() > 1 && targets.contains(&Target::All) {
+        return Err("'all' cannot be combined with other ids".to_string());
+    }
+    Ok(targets
+        .iter()
+        .flat_map(|target| match target {
+            Target::Id(id) => vec![*id],
+            Target::All => filter_todos(store, filter).iter().map(|t| t.id).collect(),
+        })
+        .collect())
+}
+
+/// Runs `op` on each id, returning the ids it succeeded for and the ids
+/// it reported missing.
+fn apply_each(
+    store: &mut TodoStore,
+    ids: &[u32],
+    mut op: impl FnMut(&mut TodoStore, u32) -> bool,
+) -> (Vec<u32>, Vec<u32>) {
+    ids.iter().partition(|&&id| op(store, id))
+}
+
+/// A parsed `list --query` expression.
+///
+/// Terms are `done`, `priority:<p>`, `due-before:<date>`, `due-after:<date>`,
+/// `tag:<name>` and `contains:<text>`, combined with `AND`, `OR`, `NOT` and parentheses.
+/// `NOT` binds tightest, then `AND`, then `OR`.
+#[derive(Debug, PartialEq)]
+enum Query {
+    Done,
+    Priority(Priority),
+    DueBefore(NaiveDate),
+    DueAfter(NaiveDate),
+    Tag(String),
+    Contains(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    fn matches(&self, todo: &Todo) -> bool {
+        let due = || parsed_due(todo);
+        match self {
+            Query::Done => todo.completed,
+            Query::Priority(p) => todo.priority == *p,
+            Query::DueBefore(date) => due().is_some_and(|d| d < *date),
+            Query::DueAfter(date) => due().is_some_and(|d| d > *date),
+            Query::Tag(tag) => has_tag(todo, tag),
+            Query::Contains(text) => title_contains(todo, text),
+            Query::Not(q) => !q.matches(todo),
+            Query::And(a, b) => a.matches(todo) && b.matches(todo),
+            Query::Or(a, b) => a.matches(todo) || b.matches(todo),
+        }
+    }
+}
+
+fn tokenize_query(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            tokens.push(c.to_string());
+            chars.next();
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' {
+                    break;
+                }
+                chars.next();
+                if c == '"' {
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => word.push(c),
+                            None => return Err("unterminated quote in query".to_string()),
+                        }
+                    }
+                } else {
+                    word.push(c);
+                }
+            }
+            tokens.push(word);
+        }
+    }
+    Ok(tokens)
+}
+
+struct QueryParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Query, String> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("OR") {
+            self.pos += 1;
+            left = Query::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, String> {
+        let mut left = self.parse_not()?;
+        while self.peek_keyword("AND") {
+            self.pos += 1;
+            left = Query::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Query, String> {
+        if self.peek_keyword("NOT") {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Query, String> {
+        let token = self
+            .next()
+            .ok_or_else(|| "unexpected end of query".to_string())?;
+        if token == "(" {
+            let inner = self.parse_or()?;
+            return match self.next().as_deref() {
+                Some(")") => Ok(inner),
+                _ => Err("missing closing parenthesis".to_string()),
+            };
+        }
+        parse_query_term(&token)
+    }
+}
+
+fn parse_query_term(token: &str) -> Result<Query, String> {
+    if token.eq_ignore_ascii_case("done") {
+        return Ok(Query::Done);
+    }
+    let (field, value) = token
+        .split_once(':')
+        .ok_or_else(|| format!("unexpected '{}' in query", token))?;
+    let parse_date = |v: &str| {
+        NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map_err(|_| format!("invalid date '{}' in query: expected YYYY-MM-DD", v))
+    };
+    match field.to_lowercase().as_str() {
+        "priority" => parse_priority(value).map(Query::Priority),
+        "due-before" => parse_date(value).map(Query::DueBefore),
+        "due-after" => parse_date(value).map(Query::DueAfter),
+        "tag" => Ok(Query::Tag(value.to_string())),
+        "contains" => Ok(Query::Contains(value.to_string())),
+        _ => Err(format!("unknown query field '{}'", field)),
+    }
+}
+
+fn parse_query(input: &str) -> Result<Query, String> {
+    let mut parser = QueryParser {
+        tokens: tokenize_query(input)?,
+        pos: 0,
+    };
+    let query = parser.parse_or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(query),
+        Some(token) => Err(format!("unexpected '{}' in query", token)),
+    }
+}
+
+/// Truncates `todos` to at most `max` rows and returns how many were cut.
+fn cap_results(todos: &mut Vec<&Todo>, max: Option<usize>) -> usize {
+    let hidden = max.map_or(0, |max| todos.len().saturating_sub(max));
+    todos.truncate(todos.len() - hidden);
+    hidden
+}
+
+fn hidden_note(hidden: usize) -> Option<String> {
+    (hidden > 0).then(|| format!("({} more not shown)", hidden))
+}
+
+/// Returns the range of the page starting at `cursor`; its end is the
+/// cursor for the following page.
+fn next_page(cursor: usize, total: usize, page_size: usize) -> std::ops::Range<usize> {
+    let start = cursor.min(total);
+    start..start.saturating_add(page_size).min(total)
+}
+
+fn priority_rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 0,
+        Priority::Medium => 1,
+        Priority::Low => 2,
+    }
+}
+
+/// Sorts by `key`, breaking ties by id.
+fn sort_todos(todos: &mut Vec<&Todo>, key: &SortKey) {
+    match key {
+        SortKey::Id => todos.sort_by_key(|t| t.id),
+        SortKey::Priority => todos.sort_by_key(|t| (priority_rank(&t.priority), t.id)),
+        SortKey::Due => todos.sort_by_key(|t| {
+            let due = parsed_due(t);
+            (due.is_none(), due, t.id)
+        }),
+        SortKey::Created => todos.sort_by(|a, b| (&a.created_at, a.id).cmp(&(&b.created_at, b.id))),
+    }
+}
+
+/// Counts todos by priority (rows: high, medium, low) and status
+/// (columns: pending, done).
+fn priority_status_matrix(todos: &[&Todo]) -> [[usize; 2]; 3] {
+    let mut matrix = [[0; 2]; 3];
+    for t in todos {
+        let row = match t.priority {
+            Priority::High => 0,
+            Priority::Medium => 1,
+            Priority::Low => 2,
+        };
+        let col = if t.completed { 1 } else { 0 };
+        matrix[row][col] += 1;
+    }
+    matrix
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    total: usize,
+    completed: usize,
+    pending: usize,
+    overdue: usize,
+    high: usize,
+    medium: usize,
+    low: usize,
+}
+
+/// Counts todos by status, and by priority across all todos.
+fn compute_stats(store: &TodoStore) -> Stats {
+    let today = Local::now().date_naive();
+    let mut stats = Stats::default();
+    for t in &store.todos {
+        stats.total += 1;
+        if t.completed {
+            stats.completed += 1;
+        } else {
+            stats.pending += 1;
+        }
+        if is_overdue(t, today) {
+            stats.overdue += 1;
+        }
+        match t.priority {
+            Priority::High => stats.high += 1,
+            Priority::Medium => stats.medium += 1,
+            Priority::Low => stats.low += 1,
+        }
+    }
+    stats
+}
+
+fn print_stats(stats: &Stats) {
+    let rows = [
+        ("Total", stats.total),
+        ("Completed", stats.completed),
+        ("Pending", stats.pending),
+        ("Overdue", stats.overdue),
+    ];
+    for (label, count) in rows {
+        println!("{:<10} {:>5}", format!("{}:", label), count);
+    }
+    println!();
+    let priorities = [
+        ("High", stats.high),
+        ("Medium", stats.medium),
+        ("Low", stats.low),
+    ];
+    for (label, count) in priorities {
+        println!("{:<10} {:>5}", format!("{}:", label), count);
+    }
+}
+
+fn todos_to_json(todos: &[&Todo]) -> String {
+    serde_json::to_string_pretty(todos).expect("Failed to serialize todos")
+}
+
+/// The whole store as `export --include-all` writes it.
+fn store_to_json(store: &TodoStore) -> String {
+    serde_json::to_string_pretty(store).expect("Failed to serialize store")
+}
+
+/// Renders a stored date or RFC 3339 timestamp as a full RFC 3339
+/// timestamp. Date-only values become local midnight with the local
+/// offset; anything unparseable is kept.
+fn iso_timestamp(value: &str) -> String {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return timestamp.to_rfc3339();
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|day| {
+            Local
+                .from_local_datetime(&day.and_time(NaiveTime::MIN))
+                .earliest()
+        })
+        .map_or_else(|| value.to_string(), |midnight| midnight.to_rfc3339())
+}
+
+/// JSON for `list --format json`. Unlike the store, `created_at` and
+/// `completed_at` are always full timestamps, and with `unwrap_single` a
+/// lone todo is printed as an object rather than an array. Fields keep the
+/// struct order (serde_json's `preserve_order`).
+fn list_json(todos: &[&Todo], unwrap_single: bool) -> String {
+    let values: Vec<serde_json::Value> = todos
+        .iter()
+        .map(|t| {
+            let mut value = serde_json::to_value(t).expect("Failed to serialize todo");
+            for key in ["created_at", "completed_at"] {
+                if let Some(serde_json::Value::String(stamp)) = value.get_mut(key) {
+                    *stamp = iso_timestamp(stamp);
+                }
+            }
+            value
+        })
+        .collect();
+    match values.as_slice() {
+        [value] if unwrap_single => serde_json::to_string_pretty(value),
+        _ => serde_json::to_string_pretty(&values),
+    }
+    .expect("Failed to serialize todos")
+}
+
+/// Appends `incoming` todos to `dest` and returns how many were added.
+/// Imported todos get ids from `dest.next_id`; without `reassign_ids` they
+/// keep their own id unless it is already taken or would leave no next id.
+/// Returns `None` if the store runs out of ids.
+fn merge_stores(dest: &mut TodoStore, incoming: TodoStore, reassign_ids: bool) -> Option<usize> {
+    let count = incoming.todos.len();
+    for mut todo in incoming.todos {
+        let taken = dest.todos.iter().any(|t| t.id == todo.id);
+        if reassign_ids || taken || todo.id == u32::MAX {
+            todo.id = dest.next_id;
+        }
+        dest.next_id = dest.next_id.max(todo.id.checked_add(1)?);
+        dest.todos.push(todo);
+    }
+    Some(count)
+}
+
+/// Prepares a whole-store import to replace the current store: ids must be
+/// unique, and `next_id` must come after all of them.
+fn restore_store(mut store: TodoStore) -> Result<TodoStore, String> {
+    let mut ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+    ids.sort_unstable();
+    if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!("duplicate todo id {}", pair[0]));
+    }
+    let after_last = match ids.last() {
+        Some(id) => id.checked_add(1).ok_or("no todo ids left")?,
+        None => 1,
+    };
+    store.next_id = store.next_id.max(after_last);
+    Ok(store)
+}
+
+/// Reads either a bare array of todos or a whole store.
+fn parse_import(data: &str) -> Result<TodoStore, serde_json::Error> {
+    if data.trim_start().starts_with('[') {
+        let todos: Vec<Todo> = serde_json::from_str(data)?;
+        Ok(TodoStore {
+            todos,
+            ..Default::default()
+        })
+    } else {
+        serde_json::from_str(data)
+    }
+}
+
+/// Quotes a CSV field when it contains a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(store: &TodoStore) -> String {
+    let mut csv = String::from("id,title,completed,priority,due_date,created_at\n");
+    for t in &store.todos {
+        let fields = [
+            t.id.to_string(),
+            csv_field(&t.title),
+            t.completed.to_string(),
+            t.priority.to_string(),
+            t.due_date.as_deref().map(csv_field).unwrap_or_default(),
+            csv_field(&t.created_at),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn print_count_table(matrix: &[[usize; 2]; 3]) {
+    println!("{:<8} {:>7} {:>7}", "Priority", "Pending", "Done");
+    println!("{}", "-".repeat(24));
+    let priorities = [Priority::High, Priority::Medium, Priority::Low];
+    for (priority, row) in priorities.iter().zip(matrix) {
+        println!("{:<8} {:>7} {:>7}", priority, row[0], row[1]);
+    }
+}
+
+const TABLE_HEADERS: [&str; 6] = ["ID", "Done", "Priority", "Due", "Tags", "Title"];
+/// Widths of every column except the last, which is never padded.
+const FIXED_WIDTHS: [usize; 5] = [5, 6, 8, 20, 16];
+/// Room left for titles in the separator under a fixed-layout header.
+const FIXED_TITLE_RULE: usize = 25;
+const MAX_AUTO_WIDTH: usize = 20;
+
+/// How table cells are rendered.
+#[derive(Debug, Clone, PartialEq)]
+struct TableStyle {
+    done_marker: String,
+    pending_marker: String,
+    color: bool,
+}
+
+impl Default for TableStyle {
+    fn default() -> Self {
+        TableStyle {
+            done_marker: "[x]".to_string(),
+            pending_marker: "[ ]".to_string(),
+            color: false,
+        }
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_OVERDUE: &str = "\x1b[1;31m";
+
+fn priority_color(priority: &Priority) -> Option<&'static str> {
+    match priority {
+        Priority::High => Some("\x1b[31m"),
+        Priority::Medium => Some("\x1b[33m"),
+        Priority::Low => None,
+    }
+}
+
+fn paint(text: &str, color: &str) -> String {
+    format!("{}{}{}", color, text, ANSI_RESET)
+}
+
+/// Colors are used only on a terminal, and never with `--no-color` or a
+/// non-empty `NO_COLOR`.
+fn color_enabled(no_color_flag: bool, no_color_env: Option<&str>, stdout_is_tty: bool) -> bool {
+    !no_color_flag && no_color_env.is_none_or(str::is_empty) && stdout_is_tty
+}
+
+fn parse_markers(s: &str) -> Result<TableStyle, String> {
+    match s.split_once(',') {
+        Some((done, pending)) if !done.is_empty() && !pending.is_empty() => Ok(TableStyle {
+            done_marker: done.to_string(),
+            pending_marker: pending.to_string(),
+            ..TableStyle::default()
+        }),
+        _ => Err(format!(
+            "invalid markers '{}': expected DONE,PENDING (e.g. \"x,-\")",
+            s
+        )),
+    }
+}
+
+/// Fixed column widths, with the Done column widened to fit long markers.
+fn fixed_widths(style: &TableStyle) -> Vec<usize> {
+    let mut widths = FIXED_WIDTHS.to_vec();
+    let marker = style.done_marker.width().max(style.pending_marker.width());
+    widths[1] = widths[1].max(marker + 1);
+    widths
+}
+
+fn table_row(t: &Todo, today: NaiveDate, style: &TableStyle) -> Vec<String> {
+    let done = if t.completed {
+        &style.done_marker
+    } else {
+        &style.pending_marker
+    };
+    vec![
+        t.id.to_string(),
+        done.to_string(),
+        t.priority.to_string(),
+        match &t.due_date {
+            Some(due) if is_overdue(t, today) => format!("{} (OVERDUE)", due),
+            Some(due) => due.clone(),
+            None => "-".to_string(),
+        },
+        if t.tags.is_empty() {
+            "-".to_string()
+        } else {
+            t.tags.join(",")
+        },
+        t.title.clone(),
+    ]
+}
+
+/// Multi-line detail view of one todo, or `None` if `id` is unknown.
+/// Optional fields are only listed when set.
+fn todo_details(store: &TodoStore, id: u32, today: NaiveDate) -> Option<String> {
+    let t = store.todos.iter().find(|t| t.id == id)?;
+    let status = match &t.completed_at {
+        Some(day) if t.completed => format!("done ({})", day),
+        _ if t.completed => "done".to_string(),
+        _ => "pending".to_string(),
+    };
+    let due = match &t.due_date {
+        Some(due) if is_overdue(t, today) => format!("{} (OVERDUE)", due),
+        Some(due) => due.clone(),
+        None => "-".to_string(),
+    };
+    let mut lines = vec![
+        format!("#{} {}", t.id, t.title),
+        format!("  Status:    {}", status),
+        format!("  Priority:  {}", t.priority),
+        format!("  Due:       {}", due),
+        format!("  Created:   {}", t.created_at),
+    ];
+    if let Some(assignee) = &t.assignee {
+        lines.push(format!("  Assignee:  {}", assignee));
+    }
+    if !t.tags.is_empty() {
+        lines.push(format!("  Tags:      {}", t.tags.join(", ")));
+    }
+    if let Some(recurrence) = t.recurrence {
+        lines.push(format!("  Repeats:   {}", recurrence));
+    }
+    if let Some(note) = &t.note {
+        lines.push(format!("  Note:      {}", note));
+    }
+    if !t.milestones.is_empty() {
+        lines.push("  Milestones:".to_string());
+        for (label, date) in &t.milestones {
+            lines.push(format!("    {} {}", date, label));
+        }
+    }
+    if !t.comments.is_empty() {
+        lines.push("  Comments:".to_string());
+        for comment in &t.comments {
+            lines.push(format!("    {}", comment));
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+/// Sizes each padded column to its widest cell (header included), capped
+/// at `MAX_AUTO_WIDTH`; `format_todo_row` cuts longer cells to fit.
+fn auto_column_widths(headers: &[&str], rows: &[Vec<String>]) -> Vec<usize> {
+    (0..headers.len() - 1)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].width())
+                .chain(std::iter::once(headers[i].width()))
+                .max()
+                .unwrap_or(0)
+                .min(MAX_AUTO_WIDTH)
+        })
+        .collect()
+}
+
+/// Shortens `cell` to `width` terminal columns, ending in "…" when cut.
+fn fit_cell(cell: &str, width: usize) -> String {
+    if cell.width() <= width {
+        return cell.to_string();
+    }
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in cell.chars() {
+        used += c.width().unwrap_or(0);
+        if used >= width {
+            break;
+        }
+        fitted.push(c);
+    }
+    fitted.push('…');
+    fitted
+}
+
+/// Pads `cell` with spaces to `width` terminal columns. `format!` padding
+/// counts chars, which misaligns wide glyphs such as emoji.
+fn pad_cell(cell: &str, width: usize) -> String {
+    format!("{}{}", cell, " ".repeat(width.saturating_sub(cell.width())))
+}
+
+/// Formats a table row, coloring the priority and overdue due-date cells
+/// when `color` is set. Padded cells wider than their column are cut to
+/// fit, and cells are padded before coloring so escape codes do not upset
+/// the alignment.
+fn format_todo_row(
+    todo: &Todo,
+    cells: &[String],
+    widths: &[usize],
+    today: NaiveDate,
+    color: bool,
+) -> String {
+    let mut cells: Vec<String> = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| match widths.get(i) {
+            Some(&width) => fit_cell(cell, width),
+            None => cell.clone(),
+        })
+        .collect();
+    if !color {
+        return format_row(&cells, widths);
+    }
+    if let Some(code) = priority_color(&todo.priority) {
+        cells[2] = paint(&pad_cell(&cells[2], widths[2]), code);
+    }
+    if is_overdue(todo, today) {
+        cells[3] = paint(&pad_cell(&cells[3], widths[3]), ANSI_OVERDUE);
+    }
+    format_row(&cells, widths)
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let mut line = String::new();
+    for (cell, width) in cells.iter().zip(widths) {
+        line.push_str(&pad_cell(cell, *width));
+        line.push(' ');
+    }
+    if let Some(last) = cells.get(widths.len()) {
+        line.push_str(last);
+    }
+    line
+}
+
+/// An optional table column, rendered between Due and Title.
+struct ExtraColumn {
+    header: &'static str,
+    fixed_width: usize,
+    cell: Box<dyn Fn(&Todo) -> String>,
+}
+
+fn completed_column() -> ExtraColumn {
+    ExtraColumn {
+        header: "Completed",
+        fixed_width: 12,
+        cell: Box::new(|t| t.completed_at.clone().unwrap_or_else(|| "-".to_string())),
+    }
+}
+
+fn assignee_column() -> ExtraColumn {
+    ExtraColumn {
+        header: "Assignee",
+        fixed_width: 12,
+        cell: Box::new(|t| t.assignee.clone().unwrap_or_else(|| "-".to_string())),
+    }
+}
+
+fn milestone_column(today: NaiveDate) -> ExtraColumn {
+    ExtraColumn {
+        header: "Milestone",
+        fixed_width: 24,
+        cell: Box::new(move |t| match next_milestone(t, today) {
+            Some((label, date)) => format!("{} ({})", label, date),
+            None => "-".to_string(),
+        }),
+    }
+}
+
+fn print_table(todos: &[&Todo], layout: &ColumnLayout, extra: &[ExtraColumn], style: &TableStyle) {
+    let title_index = TABLE_HEADERS.len() - 1;
+    let mut headers: Vec<&str> = TABLE_HEADERS.to_vec();
+    let mut fixed_widths = fixed_widths(style);
+    let today = Local::now().date_naive();
+    let mut rows: Vec<Vec<String>> = todos.iter().map(|t| table_row(t, today, style)).collect();
+    for (offset, column) in extra.iter().enumerate() {
+        headers.insert(title_index + offset, column.header);
+        fixed_widths.push(column.fixed_width);
+        for (row, todo) in rows.iter_mut().zip(todos) {
+            row.insert(title_index + offset, (column.cell)(todo));
+        }
+    }
+
+    let (widths, rule) = match layout {
+        ColumnLayout::Fixed => {
+            let rule = fixed_widths.iter().map(|w| w + 1).sum::<usize>() + FIXED_TITLE_RULE;
+            (fixed_widths, rule)
+        }
+        ColumnLayout::Auto => {
+            let widths = auto_column_widths(&headers, &rows);
+            let last = rows
+                .iter()
+                .map(|row| row[widths.len()].width())
+                .chain(std::iter::once(headers[widths.len()].len()))
+                .max()
+                .unwrap_or(0);
+            let rule = widths.iter().map(|w| w + 1).sum::<usize>() + last;
+            (widths, rule)
+        }
+    };
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    println!("{}", format_row(&headers, &widths));
+    println!("{}", "-".repeat(rule));
+    for (row, todo) in rows.iter().zip(todos) {
+        println!(
+            "{}",
+            format_todo_row(todo, row, &widths, today, style.color)
+        );
+    }
+}
+
+fn title_contains(todo: &Todo, query: &str) -> bool {
+    todo.title.to_lowercase().contains(&query.to_lowercase())
+}
+
+fn search_todos<'a>(store: &'a TodoStore, query: &str, case_sensitive: bool) -> Vec<&'a Todo> {
+    store
+        .todos
+        .iter()
+        .filter(|t| {
+            if case_sensitive {
+                t.title.contains(query)
+            } else {
+                title_contains(t, query)
+            }
+        })
+        .collect()
+}
+
+/// Finds a pending todo whose title matches `title`, ignoring case.
+fn find_duplicate<'a>(store: &'a TodoStore, title: &str) -> Option<&'a Todo> {
+    let title = title.to_lowercase();
+    store
+        .todos
+        .iter()
+        .find(|t| !t.completed && t.title.to_lowercase() == title)
+}
+
+/// Finds a title that repeats an earlier one in the same batch, ignoring
+/// case.
+fn repeated_title(titles: &[String]) -> Option<&str> {
+    let mut seen: Vec<String> = Vec::new();
+    for title in titles {
+        let lower = title.to_lowercase();
+        if seen.contains(&lower) {
+            return Some(title);
+        }
+        seen.push(lower);
+    }
+    None
+}
+
+/// Keeps todos whose lowercased title occurs more than once, grouping
+/// each set of duplicates together in order of first appearance.
+fn duplicate_todos<'a>(todos: &[&'a Todo]) -> Vec<&'a Todo> {
+    let mut groups: Vec<(String, Vec<&'a Todo>)> = Vec::new();
+    for &todo in todos {
+        let key = todo.title.to_lowercase();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(todo),
+            None => groups.push((key, vec![todo])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .flat_map(|(_, group)| group)
+        .collect()
+}
+
+fn has_tag(todo: &Todo, tag: &str) -> bool {
+    todo.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+fn assigned_to(todo: &Todo, name: &str) -> bool {
+    todo.assignee
+        .as_deref()
+        .is_some_and(|a| a.eq_ignore_ascii_case(name))
+}
+
+/// Longest span `parse_relative_days` accepts, about a century.
+const MAX_RELATIVE_DAYS: i64 = 36_500;
+
+/// Parses a relative span such as `3d`, `2w` or `1m` (30 days) into days.
+fn parse_relative_days(s: &str) -> Result<i64, String> {
+    let err = || format!("invalid relative date '{}': expected e.g. 7d, 2w or 1m", s);
+    let (split, _) = s.char_indices().last().ok_or_else(err)?;
+    let (count, unit) = s.split_at(split);
+    let count: i64 = count.parse().map_err(|_| err())?;
+    let per_unit = match unit {
+        "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        _ => return Err(err()),
+    };
+    if count < 0 {
+        return Err(err());
+    }
+    match count.checked_mul(per_unit) {
+        Some(days) if days <= MAX_RELATIVE_DAYS => Ok(days),
+        _ => Err(format!(
+            "relative date '{}' is too far back: at most {} days",
+            s, MAX_RELATIVE_DAYS
+        )),
+    }
+}
+
+/// A todo created on or after `days` before `today`. Spans reaching past
+/// the earliest representable date have no lower bound.
+fn created_since(todo: &Todo, today: NaiveDate, days: i64) -> bool {
+    let Ok(created) = NaiveDate::parse_from_str(&todo.created_at, "%Y-%m-%d") else {
+        return false;
+    };
+    today
+        .checked_sub_days(Days::new(days.unsigned_abs()))
+        .is_none_or(|cutoff| created >= cutoff)
+}
+
+fn parsed_due(todo: &Todo) -> Option<NaiveDate> {
+    todo.due_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+fn due_soon(todo: &Todo, today: NaiveDate, days: u32) -> bool {
+    if todo.completed || todo.silent {
+        return false;
+    }
+    let Some(due) = parsed_due(todo) else {
+        return false;
+    };
+    // A window past the latest representable date has no upper bound.
+    due >= today
+        && today
+            .checked_add_days(Days::new(days.into()))
+            .is_none_or(|end| due <= end)
+}
+
+/// A pending todo whose due date is strictly before `today`.
+fn is_overdue(todo: &Todo, today: NaiveDate) -> bool {
+    !todo.completed && !todo.silent && parsed_due(todo).is_some_and(|due| due < today)
+}
+
+/// How many days past its due date a pending todo is, or `None` if it is
+/// not overdue.
+fn days_overdue(todo: &Todo, today: NaiveDate) -> Option<i64> {
+    if !is_overdue(todo, today) {
+        return None;
+    }
+    parsed_due(todo).map(|due| (today - due).num_days())
+}
+
+fn assemble_add_answers(
+    title: &str,
+    priority: &str,
+    due: &str,
+) -> Result<(String, Priority, Option<String>), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Title cannot be empty.".to_string());
+    }
+    let priority = match priority.trim() {
+        "" => Priority::Medium,
+        p => parse_priority(p)?,
+    };
+    let due = match due.trim() {
+        "" => None,
+        d => Some(d.to_string()),
+    };
+    Ok((title.to_string(), priority, due))
+}
+
+fn prompt_line(label: &str) -> Result<String, StoreError> {
+    print!("{}", label);
+    let mut line = String::new();
+    io::stdout()
+        .flush()
+        .and_then(|()| io::stdin().read_line(&mut line))
+        .map_err(StoreError::Prompt)?;
+    Ok(line.trim().to_string())
+}
+
+fn confirm(question: &str) -> Result<bool, StoreError> {
+    let answer = prompt_line(&format!("{} [y/N] ", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(cli: Cli) -> Result<(), StoreError> {
+    let path = store_path(cli.global)?;
+    let style = TableStyle {
+        color: color_enabled(
+            cli.no_color,
+            std::env::var("NO_COLOR").ok().as_deref(),
+            io::stdout().is_terminal(),
+        ),
+        ..cli.markers.clone().unwrap_or_default()
+    };
+    // Commands that use the store load it through here, so `--no-create`
+    // and `--escalate-after` only apply to them.
+    let load = || -> Result<TodoStore, StoreError> {
+        let mut store = open_store(&path, cli.no_create)?;
+        let Some(days) = cli.escalate_after else {
+            return Ok(store);
+        };
+        let count = escalate_stale(&mut store, Local::now().date_naive(), days);
+        if count > 0 {
+            // Not saved here: commands that edit the store save it with
+            // their own change through `persist`, so undo reverts both.
+            // Read-only commands and list paging only show it.
+            // stderr keeps stdout clean for the command's own output.
+            eprintln!("Escalated {} stale todo(s).", count);
+        }
+        Ok(store)
+    };
+
+    match cli.command {
+        Commands::Add {
+            titles,
+            priority,
+            due,
+            assignee,
+            tags,
+            milestones,
+            interactive,
+            no_duplicates,
+            repeat,
+            note,
+            silent,
+        } => {
+            let (titles, priority, due) = if interactive {
+                if !io::stdin().is_terminal() {
+                    return Err(StoreError::Input(
+                        "--interactive needs a terminal; pass the title as an argument instead."
+                            .into(),
+                    ));
+                }
+                let answers = assemble_add_answers(
+                    &prompt_line("Title: ")?,
+                    &prompt_line("Priority (high/medium/low) [medium]: ")?,
+                    &prompt_line("Due date (YYYY-MM-DD, optional): ")?,
+                );
+                let (title, priority, due) = answers.map_err(StoreError::Input)?;
+                (vec![title], priority, due)
+            } else {
+                (titles, priority, due)
+            };
+            if titles.iter().any(|t| t.trim().is_empty()) {
+                return Err(StoreError::Input("Title cannot be empty.".into()));
+            }
+            let due = due
+                .map(|d| parse_due_date(&d))
+                .transpose()
+                .map_err(StoreError::Input)?;
+            let mut store = load()?;
+            if no_duplicates {
+                for title in &titles {
+                    if let Some(existing) = find_duplicate(&store, title) {
+                        return Err(StoreError::Input(format!(
+                            "Todo #{} already has the title \"{}\".",
+                            existing.id, existing.title
+                        )));
+                    }
+                }
+                if let Some(title) = repeated_title(&titles) {
+                    return Err(StoreError::Input(format!(
+                        "The title \"{}\" is given more than once.",
+                        title
+                    )));
+                }
+            }
+            let ids = add_todos(&mut store, &titles, priority, due);
+            for &id in &ids {
+                if let Some(todo) = find_todo_mut(&mut store, id) {
+                    todo.assignee = assignee.clone();
+                    todo.tags = tags.clone();
+                    todo.milestones = milestones.clone();
+                    todo.recurrence = repeat;
+                    todo.note = note.clone();
+                    todo.silent = silent;
+                }
+            }
+            if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                for (id, title) in ids.iter().zip(&titles) {
+                    println!("Added todo #{}: {}", id, title);
+                }
+            }
+        }
+        Commands::List {
+            filter,
+            contains,
+            assignee,
+            tag,
+            duplicates,
+            due_within,
+            overdue_days,
+            since,
+            query,
+            sort,
+            reverse,
+            newest,
+            oldest,
+            format,
+            limit,
+            unwrap_single,
+            columns,
+            next,
+            page_size,
+            reset_cursor,
+            show_completed_at,
+            milestones,
+            wide,
+        } => {
+            let mut store = load()?;
+            if reset_cursor {
+                store.list_cursor = 0;
+                if !next {
+                    if save_list_cursor(&path, 0, cli.compact_store, cli.dry_run)? {
+                        println!("List cursor reset.");
+                    }
+                    return Ok(());
+                }
+            }
+            // The count table counts the filtered todos; it has a Done
+            // column, so without --filter it starts from every todo.
+            let count_table = format == OutputFormat::CountTable;
+            let filter = list_filter(filter, query.is_some() || count_table);
+            let mut todos = filter_todos(&store, &filter);
+            if let Some(query) = &contains {
+                todos.retain(|t| title_contains(t, query));
+            }
+            if let Some(name) = &assignee {
+                todos.retain(|t| assigned_to(t, name));
+            }
+            if let Some(tag) = &tag {
+                todos.retain(|t| has_tag(t, tag));
+            }
+            if let Some(days) = due_within {
+                let today = Local::now().date_naive();
+                todos.retain(|t| due_soon(t, today, days));
+            }
+            if let Some(min) = overdue_days {
+                let today = Local::now().date_naive();
+                todos.retain(|t| days_overdue(t, today).is_some_and(|days| days >= min));
+            }
+            if let Some(days) = since {
+                let today = Local::now().date_naive();
+                todos.retain(|t| created_since(t, today, days));
+            }
+            if let Some(query) = &query {
+                let query = parse_query(query)
+                    .map_err(|e| StoreError::Input(format!("Invalid query: {}", e)))?;
+                todos.retain(|t| query.matches(t));
+            }
+            let (sort, reverse) = if newest || oldest {
+                (SortKey::Created, newest)
+            } else {
+                (sort, reverse)
+            };
+            sort_todos(&mut todos, &sort);
+            if reverse {
+                todos.reverse();
+            }
+            if duplicates {
+                todos = duplicate_todos(&todos);
+            }
+            if count_table {
+                print_count_table(&priority_status_matrix(&todos));
+                return Ok(());
+            }
+            cap_results(&mut todos, limit);
+            let hidden = if next {
+                0
+            } else {
+                cap_results(&mut todos, cli.max_results)
+            };
+            if format == OutputFormat::Json {
+                println!("{}", list_json(&todos, unwrap_single));
+                if let Some(note) = hidden_note(hidden) {
+                    eprintln!("{}", note);
+                }
+                return Ok(());
+            }
+
+            let mut extra = Vec::new();
+            if show_completed_at {
+                extra.push(completed_column());
+            }
+            if milestones {
+                extra.push(milestone_column(Local::now().date_naive()));
+            }
+            if wide {
+                extra.push(assignee_column());
+            }
+
+            if next {
+                let page = next_page(store.list_cursor, todos.len(), page_size);
+                let total = todos.len();
+                let shown = todos[page.clone()].to_vec();
+                if shown.is_empty() {
+                    println!("No more todos. Use --reset-cursor to start over.");
+                } else {
+                    print_table(&shown, &columns, &extra, &style);
+                    println!("Showing {}-{} of {}.", page.start + 1, page.end, total);
+                }
+                // Paging is not an edit; keep the undo backup for real changes.
+                save_list_cursor(&path, page.end, cli.compact_store, cli.dry_run)?;
+                return Ok(());
+            }
+
+            if todos.is_empty() {
+                println!("No todos found.");
+                return Ok(());
+            }
+
+            print_table(&todos, &columns, &extra, &style);
+            if let Some(note) = hidden_note(hidden) {
+                println!("{}", note);
+            }
+        }
+        Commands::Overdue => {
+            let store = load()?;
+            let today = Local::now().date_naive();
+            let todos: Vec<&Todo> = store
+                .todos
+                .iter()
+                .filter(|t| is_overdue(t, today))
+                .collect();
+            if todos.is_empty() {
+                println!("No overdue todos.");
+                return Ok(());
+            }
+            print_table(&todos, &ColumnLayout::Fixed, &[], &style);
+        }
+        Commands::Show { id } => {
+            let store = load()?;
+            match todo_details(&store, id, Local::now().date_naive()) {
+                Some(details) => println!("{}", details),
+                None => return Err(not_found(&[id])),
+            }
+        }
+        Commands::Search {
+            query,
+            case_sensitive,
+        } => {
+            let store = load()?;
+            let mut todos = search_todos(&store, &query, case_sensitive);
+            if todos.is_empty() {
+                println!("No todos found.");
+                return Ok(());
+            }
+            let hidden = cap_results(&mut todos, cli.max_results);
+            print_table(&todos, &ColumnLayout::Fixed, &[completed_column()], &style);
+            if let Some(note) = hidden_note(hidden) {
+                println!("{}", note);
+            }
+        }
+        Commands::Done { targets, filter } => {
+            let mut store = load()?;
+            let ids = expand_targets(&store, &targets, &filter).map_err(StoreError::Input)?;
+            let today = Local::now().date_naive();
+            let mut completed_any = false;
+            let mut scheduled = Vec::new();
+            let mut unscheduled = Vec::new();
+            let (done, missing) = apply_each(&mut store, &ids, |store, id| {
+                let was_pending = store.todos.iter().any(|t| t.id == id && !t.completed);
+                let found = mark_done(store, id);
+                if found && was_pending {
+                    completed_any = true;
+                    match schedule_next(store, id, today) {
+                        Ok(next) => scheduled.extend(next),
+                        Err(e) => unscheduled.push(e),
+                    }
+                }
+                found
+            });
+            if completed_any {
+                record_completion(&mut store, today);
+            }
+            let saved = !done.is_empty() && persist(&store, &path, cli.compact_store, cli.dry_run)?;
+            if ids.is_empty() {
+                println!("No todos matched.");
+            }
+            // Under --dry-run the plan already says what would change.
+            if saved {
+                for id in &done {
+                    println!("Marked todo #{} as done.", id);
+                }
+                for &id in &scheduled {
+                    if let Some(next) = store.todos.iter().find(|t| t.id == id) {
+                        let due = next.due_date.as_deref().unwrap_or("-");
+                        println!("Scheduled next occurrence as #{} (due {}).", id, due);
+                    }
+                }
+            }
+            for message in &unscheduled {
+                eprintln!("{}", message);
+            }
+            if !missing.is_empty() {
+                return Err(not_found(&missing));
+            }
+        }
+        Commands::Remove {
+            before: Some(before),
+            yes,
+            ..
+        } => {
+            let cutoff = NaiveDate::parse_from_str(&before, "%Y-%m-%d").map_err(|_| {
+                StoreError::Input(format!("Invalid date '{}': expected YYYY-MM-DD.", before))
+            })?;
+            let mut store = load()?;
+            let count = store
+                .todos
+                .iter()
+                .filter(|t| created_before(t, cutoff))
+                .count();
+            if count == 0 {
+                println!("No todos created before {}.", before);
+                return Ok(());
+            }
+            let question = format!("Remove {} todo(s) created before {}?", count, before);
+            if !yes && !cli.dry_run && !confirm(&question)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let removed = remove_created_before(&mut store, cutoff);
+            if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                println!("Removed {} todo(s) created before {}.", removed, before);
+            }
+        }
+        Commands::ClearDone { yes } => {
+            let mut store = load()?;
+            let count = store.todos.iter().filter(|t| t.completed).count();
+            if count == 0 {
+                println!("No completed todos to remove.");
+                return Ok(());
+            }
+            let question = format!("Remove {} completed todo(s)?", count);
+            if !yes && !cli.dry_run && !confirm(&question)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let removed = clear_done(&mut store);
+            if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                println!("Removed {} completed todo(s).", removed);
+            }
+        }
+        Commands::Undone { id } => {
+            let mut store = load()?;
+            if mark_undone(&mut store, id) {
+                if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                    println!("Marked todo #{} as pending.", id);
+                }
+            } else {
+                return Err(not_found(&[id]));
+            }
+        }
+        Commands::Comment { id, text } => {
+            if text.trim().is_empty() {
+                return Err(StoreError::Input("Comment cannot be empty.".into()));
+            }
+            let mut store = load()?;
+            let stamp = Local::now().format("%Y-%m-%d %H:%M").to_string();
+            if add_comment(&mut store, id, text.trim(), &stamp) {
+                if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                    println!("Commented on todo #{}.", id);
+                }
+            } else {
+                return Err(not_found(&[id]));
+            }
+        }
+        Commands::Edit {
+            id,
+            title: None,
+            priority: None,
+            due: None,
+            assignee: None,
+        } => {
+            if !io::stdin().is_terminal() {
+                return Err(StoreError::Input(
+                    "Nothing to edit: pass --title, --priority, --due, or --assignee.".into(),
+                ));
+            }
+            let mut store = load()?;
+            let Some(todo) = find_todo_mut(&mut store, id) else {
+                return Err(not_found(&[id]));
+            };
+            println!("Editing todo #{}:", id);
+            println!("  title:    {}", todo.title);
+            println!("  priority: {}", todo.priority);
+            println!("  due:      {}", todo.due_date.as_deref().unwrap_or("-"));
+            println!("  assignee: {}", todo.assignee.as_deref().unwrap_or("-"));
+            let field = prompt_line("Field to change (title/priority/due/assignee): ")?;
+            let value = prompt_line("New value: ")?;
+            if let Err(e) = apply_field_edit(todo, &field, &value) {
+                return Err(StoreError::Input(e));
+            }
+            if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                println!("Updated todo #{}.", id);
+            }
+        }
+        Commands::Edit {
+            id,
+            title,
+            priority,
+            due,
+            assignee,
+        } => {
+            let due = due
+                .map(|d| {
+                    if d.is_empty() {
+                        Ok(d)
+                    } else {
+                        parse_due_date(&d)
+                    }
+                })
+                .transpose()
+                .map_err(StoreError::Input)?;
+            let mut store = load()?;
+            if edit_todo(&mut store, id, title, priority, due) {
+                if let (Some(assignee), Some(todo)) = (assignee, find_todo_mut(&mut store, id)) {
+                    todo.assignee = if assignee.is_empty() {
+                        None
+                    } else {
+                        Some(assignee)
+                    };
+                }
+                if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                    println!("Updated todo #{}.", id);
+                }
+            } else {
+                return Err(not_found(&[id]));
+            }
+        }
+        Commands::Renumber { yes } => {
+            let mut store = load()?;
+            let question = format!(
+                "Renumber {} todo(s)? Existing ids will change.",
+                store.todos.len()
+            );
+            if !yes && !cli.dry_run && !confirm(&question)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let changed = renumber(&mut store);
+            // Shown even with --yes, since scripts may hold on to old ids.
+            if !changed.is_empty() {
+                eprintln!("Existing ids change:");
+                for (old, new) in &changed {
+                    eprintln!("  #{} -> #{}", old, new);
+                }
+            }
+            if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                println!("Renumbered {} todo(s).", store.todos.len());
+            }
+        }
+        Commands::Undo => {
+            if restore_backup(&path, cli.compact_store, cli.dry_run)? {
+                if !cli.dry_run {
+                    println!("Reverted the last change.");
+                }
+            } else {
+                println!("Nothing to undo.");
+            }
+        }
+        Commands::Import {
+            path: import_path,
+            reassign_ids,
+            replace,
+        } => {
+            let incoming = fs::read_to_string(&import_path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| parse_import(&data).map_err(|e| e.to_string()))
+                .map_err(|e| {
+                    StoreError::Input(format!("Cannot import {}: {}", import_path.display(), e))
+                })?;
+            if replace {
+                if cli.no_create {
+                    require_store(&path)?;
+                }
+                let store = restore_store(incoming).map_err(|e| {
+                    StoreError::Input(format!("Cannot import {}: {}", import_path.display(), e))
+                })?;
+                if persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                    println!("Replaced the store with {} todo(s).", store.todos.len());
+                }
+                return Ok(());
+            }
+            let mut store = load()?;
+            let count = merge_stores(&mut store, incoming, reassign_ids).ok_or_else(|| {
+                StoreError::Input(format!(
+                    "Cannot import {}: no todo ids left",
+                    import_path.display()
+                ))
+            })?;
+            if count > 0 && !persist(&store, &path, cli.compact_store, cli.dry_run)? {
+                return Ok(());
+            }
+            println!("Imported {} todo(s).", count);
+        }
+        Commands::Export {
+            format,
+            out,
+            include_all,
+        } => {
+            if include_all && format != ExportFormat::Json {
+                return Err(StoreError::Input(
+                    "--include-all needs --format json.".into(),
+                ));
+            }
+            let store = load()?;
+            let data = match format {
+                ExportFormat::Csv => to_csv(&store),
+                ExportFormat::Json if include_all => store_to_json(&store) + "\n",
+                ExportFormat::Json => {
+                    let todos: Vec<&Todo> = store.todos.iter().collect();
+                    todos_to_json(&todos) + "\n"
+                }
+            };
+            match out {
+                Some(out) => {
+                    if let Err(e) = fs::write(&out, data) {
+                        return Err(StoreError::Input(format!(
+                            "Cannot write {}: {}",
+                            out.display(),
+                            e
+                        )));
+                    }
+                    println!(
+                        "Exported {} todo(s) to {}.",
+                        store.todos.len(),
+                        out.display()
+                    );
+                }
+                None => print!("{}", data),
+            }
+        }
+        Commands::Where { format } => {
+            let info = store_info(&path)?;
+            match format {
+                InfoFormat::Text => {
+                    let state = if info.exists {
+                        ""
+                    } else {
+                        " (not created yet)"
+                    };
+                    println!("{}{}", info.path, state);
+                }
+                InfoFormat::Json => {
+                    let json = serde_json::to_string_pretty(&info)
+                        .expect("Failed to serialize store info");
+                    println!("{}", json);
+                }
+            }
+        }
+        Commands::Schema => {
+            let schema =
+                serde_json::to_string_pretty(&store_schema()).expect("Failed to serialize schema");
+            println!("{}", schema);
+        }
+        Commands::Streak => {
+            let store = load()?;
+            let last = store
+                .last_completion_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            let streak = active_streak(store.completion_streak, last, Local::now().date_naive());
+            println!("Current streak: {} day(s)", streak);
+            if let Some(last) = &store.last_completion_date {
+                println!("Last completion: {}", last);
+            }
+        }
+        Commands::Stats { streak_calendar } => {
+            let store = load()?;
+            print_stats(&compute_stats(&store));
+            if streak_calendar {
+                let (start, end) = month_bounds(Local::now().date_naive());
+                println!();
+                println!(
+                    "{}",
+                    render_streak_calendar(&completions_by_day(&store, start, end))
+                );
+            }
+        }
+        Commands::Digest { .. } => {
+            let store = load()?;
+            let (start, end) = week_bounds(Local::now().date_naive());
+            let days = completions_by_day_between(&store, start, end);
+            println!("Completed this week ({} to {}):", start, end);
+            if days.is_empty() {
+                println!("Nothing completed yet.");
+                return Ok(());
+            }
+            for (day, todos) in days {
+                println!();
+                println!("{}", day.format("%A %Y-%m-%d"));
+                for t in todos {
+                    println!("  #{} [{}] {}", t.id, t.priority, t.title);
+                }
+            }
+        }
+        Commands::Remove {
+            targets,
+            filter,
+            yes,
+            ..
+        } => {
+            if targets.contains(&Target::All) && !yes {
+                return Err(StoreError::Input(
+                    "Refusing to remove all without --yes.".into(),
+                ));
+            }
+            let mut store = load()?;
+            let ids = expand_targets(&store, &targets, &filter).map_err(StoreError::Input)?;
+            let (removed, missing) = apply_each(&mut store, &ids, remove_todo);
+            let saved =
+                !removed.is_empty() && persist(&store, &path, cli.compact_store, cli.dry_run)?;
+            if ids.is_empty() {
+                println!("No todos matched.");
+            }
+            if saved {
+                for id in &removed {
+                    println!("Removed todo #{}.", id);
+                }
+            }
+            if !missing.is_empty() {
+                return Err(not_found(&missing));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn empty_store() -> TodoStore {
+        TodoStore {
+            next_id: 1,
+            ..Default::default()
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "todo-cli-test-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        path
+    }
+
+    // -- add_todo tests --
+
+    #[test]
+    fn add_todo_assigns_incrementing_ids() {
+        let mut store = empty_store();
+        let id1 = add_todo(&mut store, "First".into(), Priority::Low, None);
+        let id2 = add_todo(&mut store, "Second".into(), Priority::High, None);
+        assert_eq!(id1, 1);
         assert_eq!(id2, 2);
         assert_eq!(store.next_id, 3);
     }
 
     #[test]
-    fn add_todo_stores_fields_correctly() {
+    fn add_todo_stores_fields_correctly() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Buy milk".into(),
+            Priority::High,
+            Some("2026-03-01".into()),
+        );
+        assert_eq!(store.todos.len(), 1);
+        let todo = &store.todos[0];
+        assert_eq!(todo.title, "Buy milk");
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.due_date.as_deref(), Some("2026-03-01"));
+        assert!(!todo.completed);
+    }
+
+    #[test]
+    fn add_todo_defaults_to_not_completed() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        assert!(!store.todos[0].completed);
+    }
+
+    #[test]
+    fn add_todos_assigns_sequential_ids_with_shared_fields() {
+        let mut store = empty_store();
+        let titles = vec!["Task A".to_string(), "Task B".into(), "Task C".into()];
+        let ids = add_todos(
+            &mut store,
+            &titles,
+            Priority::High,
+            Some("2026-03-01".into()),
+        );
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(store.todos.len(), 3);
+        assert!(store
+            .todos
+            .iter()
+            .all(|t| t.priority == Priority::High && t.due_date.as_deref() == Some("2026-03-01")));
+        assert_eq!(store.todos[2].title, "Task C");
+    }
+
+    #[test]
+    fn add_requires_at_least_one_title() {
+        assert!(Cli::try_parse_from(["todo-cli", "add"]).is_err());
+        let cli = Cli::try_parse_from(["todo-cli", "add", "A", "B", "C"]).unwrap();
+        match cli.command {
+            Commands::Add { titles, .. } => assert_eq!(titles, vec!["A", "B", "C"]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn interactive_add_rejects_title_priority_and_due_args() {
+        for extra in [
+            &["Task"][..],
+            &["--priority", "high"],
+            &["--due", "2026-03-01"],
+        ] {
+            let args = ["todo-cli", "add", "--interactive"].iter().chain(extra);
+            assert!(Cli::try_parse_from(args).is_err(), "{:?}", extra);
+        }
+        let cli = Cli::try_parse_from(["todo-cli", "add", "--interactive", "--tag", "home"]);
+        assert!(cli.is_ok());
+    }
+
+    // -- milestone tests --
+
+    #[test]
+    fn parse_milestone_accepts_label_and_date() {
+        assert_eq!(
+            parse_milestone("draft:2026-03-01"),
+            Ok(("draft".to_string(), "2026-03-01".to_string()))
+        );
+        assert!(parse_milestone("draft").is_err());
+        assert!(parse_milestone(":2026-03-01").is_err());
+        assert!(parse_milestone("draft:soon").is_err());
+    }
+
+    #[test]
+    fn next_milestone_picks_nearest_upcoming() {
+        let cli = Cli::try_parse_from([
+            "todo-cli",
+            "add",
+            "Thesis",
+            "--milestone",
+            "final:2026-06-01",
+            "--milestone",
+            "draft:2026-03-01",
+        ])
+        .unwrap();
+        let Commands::Add { milestones, .. } = cli.command else {
+            unreachable!()
+        };
+        let mut store = empty_store();
+        add_todo(&mut store, "Thesis".into(), Priority::High, None);
+        store.todos[0].milestones = milestones;
+
+        assert_eq!(store.todos[0].milestones.len(), 2);
+        let next = next_milestone(&store.todos[0], date("2026-02-15")).unwrap();
+        assert_eq!(next.0, "draft");
+        let next = next_milestone(&store.todos[0], date("2026-03-02")).unwrap();
+        assert_eq!(next.0, "final");
+        assert!(next_milestone(&store.todos[0], date("2026-07-01")).is_none());
+
+        let column = milestone_column(date("2026-02-15"));
+        assert_eq!((column.cell)(&store.todos[0]), "draft (2026-03-01)");
+    }
+
+    // -- assemble_add_answers tests --
+
+    #[test]
+    fn assemble_add_answers_uses_given_values() {
+        let answers = assemble_add_answers(" Buy milk ", "high", "2026-03-01").unwrap();
+        assert_eq!(
+            answers,
+            (
+                "Buy milk".to_string(),
+                Priority::High,
+                Some("2026-03-01".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn assemble_add_answers_defaults_priority_and_due() {
+        let answers = assemble_add_answers("Task", "", "").unwrap();
+        assert_eq!(answers, ("Task".to_string(), Priority::Medium, None));
+    }
+
+    #[test]
+    fn assemble_add_answers_rejects_bad_input() {
+        assert!(assemble_add_answers("  ", "", "").is_err());
+        assert!(assemble_add_answers("Task", "urgent", "").is_err());
+    }
+
+    // -- parse_priority tests --
+
+    fn parse_add_priority(value: &str) -> Result<Priority, clap::Error> {
+        let cli = Cli::try_parse_from(["todo-cli", "add", "Task", "--priority", value])?;
+        match cli.command {
+            Commands::Add { priority, .. } => Ok(priority),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn priority_accepts_numeric_aliases() {
+        assert_eq!(parse_add_priority("1").unwrap(), Priority::High);
+        assert_eq!(parse_add_priority("2").unwrap(), Priority::Medium);
+        assert_eq!(parse_add_priority("3").unwrap(), Priority::Low);
+    }
+
+    #[test]
+    fn priority_still_accepts_words() {
+        assert_eq!(parse_add_priority("high").unwrap(), Priority::High);
+        assert_eq!(parse_add_priority("LOW").unwrap(), Priority::Low);
+    }
+
+    #[test]
+    fn priority_rejects_unknown_numbers() {
+        assert!(parse_add_priority("4").is_err());
+        assert!(parse_priority("0").is_err());
+    }
+
+    // -- parse_due_date tests --
+
+    #[test]
+    fn parse_due_date_accepts_valid_date() {
+        assert_eq!(parse_due_date("2026-03-01"), Ok("2026-03-01".to_string()));
+        assert_eq!(parse_due_date("2026-3-1"), Ok("2026-03-01".to_string()));
+    }
+
+    #[test]
+    fn parse_due_date_rejects_bad_format() {
+        let err = parse_due_date("notadate").unwrap_err();
+        assert!(err.contains("notadate"));
+        assert!(parse_due_date("03/01/2026").is_err());
+    }
+
+    #[test]
+    fn parse_due_date_rejects_impossible_date() {
+        assert!(parse_due_date("2026-13-40").is_err());
+        assert!(parse_due_date("2026-02-30").is_err());
+    }
+
+    // -- mark_done tests --
+
+    #[test]
+    fn mark_done_existing_todo() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        assert!(mark_done(&mut store, 1));
+        assert!(store.todos[0].completed);
+    }
+
+    #[test]
+    fn mark_done_records_completed_at() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        assert_eq!(store.todos[0].completed_at, None);
+        mark_done(&mut store, 1);
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(store.todos[0].completed_at.as_deref(), Some(today.as_str()));
+    }
+
+    #[test]
+    fn mark_done_keeps_original_completed_at() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        mark_done(&mut store, 1);
+        store.todos[0].completed_at = Some("2026-01-01".into());
+        mark_done(&mut store, 1);
+        assert_eq!(store.todos[0].completed_at.as_deref(), Some("2026-01-01"));
+    }
+
+    #[test]
+    fn mark_done_nonexistent_returns_false() {
+        let mut store = empty_store();
+        assert!(!mark_done(&mut store, 99));
+    }
+
+    #[test]
+    fn mark_done_idempotent() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        assert!(mark_done(&mut store, 1));
+        assert!(mark_done(&mut store, 1));
+        assert!(store.todos[0].completed);
+    }
+
+    #[test]
+    fn done_all_pending_completes_every_pending_todo() {
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(&mut store, "B".into(), Priority::High, None);
+        add_todo(&mut store, "C".into(), Priority::Medium, None);
+        mark_done(&mut store, 2);
+        let ids = expand_targets(&store, &[Target::All], &ListFilter::Pending).unwrap();
+        assert_eq!(ids, vec![1, 3]);
+        for id in ids {
+            assert!(mark_done(&mut store, id));
+        }
+        assert!(store.todos.iter().all(|t| t.completed));
+    }
+
+    #[test]
+    fn expand_targets_rejects_all_mixed_with_ids() {
+        let store = empty_store();
+        assert!(expand_targets(&store, &[Target::Id(1), Target::All], &ListFilter::All).is_err());
+        assert_eq!(
+            expand_targets(&store, &[Target::Id(4), Target::Id(2)], &ListFilter::All),
+            Ok(vec![4, 2])
+        );
+    }
+
+    fn bulk_store(path: &Path) -> TodoStore {
+        let mut store = empty_store();
+        for title in ["A", "B", "C"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        save_store(&store, path, false).unwrap();
+        store
+    }
+
+    #[test]
+    fn bulk_done_all_valid_ids() {
+        let path = temp_path("bulk-done-valid");
+        let mut store = bulk_store(&path);
+        let (done, missing) = apply_each(&mut store, &[1, 3], mark_done);
+        save_store(&store, &path, false).unwrap();
+
+        assert_eq!((done, missing), (vec![1, 3], vec![]));
+        let saved = load_store(&path).unwrap();
+        let completed: Vec<bool> = saved.todos.iter().map(|t| t.completed).collect();
+        assert_eq!(completed, vec![true, false, true]);
+        fs::remove_file(&path).ok();
+        fs::remove_file(backup_store_path(&path)).ok();
+    }
+
+    #[test]
+    fn bulk_done_partially_valid_ids_applies_the_valid_ones() {
+        let path = temp_path("bulk-done-partial");
+        let mut store = bulk_store(&path);
+        let (done, missing) = apply_each(&mut store, &[2, 9, 3], mark_done);
+        save_store(&store, &path, false).unwrap();
+
+        assert_eq!((done, missing), (vec![2, 3], vec![9]));
+        let saved = load_store(&path).unwrap();
+        let completed: Vec<bool> = saved.todos.iter().map(|t| t.completed).collect();
+        assert_eq!(completed, vec![false, true, true]);
+        fs::remove_file(&path).ok();
+        fs::remove_file(backup_store_path(&path)).ok();
+    }
+
+    #[test]
+    fn bulk_remove_all_missing_ids_changes_nothing() {
+        let path = temp_path("bulk-remove-missing");
+        let mut store = bulk_store(&path);
+        let (removed, missing) = apply_each(&mut store, &[7, 8], remove_todo);
+
+        assert_eq!((removed, missing), (vec![], vec![7, 8]));
+        assert_eq!(load_store(&path).unwrap(), store);
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn bulk_remove_partially_valid_ids() {
+        let mut store = empty_store();
+        for title in ["A", "B", "C"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        let (removed, missing) = apply_each(&mut store, &[1, 5, 3], remove_todo);
+        assert_eq!((removed, missing), (vec![1, 3], vec![5]));
+        let ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn parse_target_accepts_ids_and_all() {
+        assert_eq!(parse_target("7"), Ok(Target::Id(7)));
+        assert_eq!(parse_target("all"), Ok(Target::All));
+        assert!(parse_target("every").is_err());
+    }
+
+    // -- recurrence tests --
+
+    #[test]
+    fn advance_date_steps_each_interval() {
+        let day = date("2026-03-10");
+        assert_eq!(
+            advance_date(day, Recurrence::Daily),
+            Some(date("2026-03-11"))
+        );
+        assert_eq!(
+            advance_date(day, Recurrence::Weekly),
+            Some(date("2026-03-17"))
+        );
+        assert_eq!(
+            advance_date(day, Recurrence::Monthly),
+            Some(date("2026-04-10"))
+        );
+    }
+
+    #[test]
+    fn advance_date_monthly_clamps_to_month_end() {
+        assert_eq!(
+            advance_date(date("2026-01-31"), Recurrence::Monthly),
+            Some(date("2026-02-28"))
+        );
+        assert_eq!(
+            advance_date(date("2028-01-31"), Recurrence::Monthly),
+            Some(date("2028-02-29"))
+        );
+        assert_eq!(
+            advance_date(date("2026-12-31"), Recurrence::Monthly),
+            Some(date("2027-01-31"))
+        );
+    }
+
+    #[test]
+    fn advance_date_stops_at_year_9999() {
+        let last = date("9999-12-31");
+        assert_eq!(advance_date(last, Recurrence::Daily), None);
+        assert_eq!(advance_date(last, Recurrence::Weekly), None);
+        assert_eq!(advance_date(last, Recurrence::Monthly), None);
+        assert_eq!(advance_date(NaiveDate::MAX, Recurrence::Daily), None);
+        assert_eq!(
+            advance_date(date("9999-12-30"), Recurrence::Daily),
+            Some(last)
+        );
+    }
+
+    #[test]
+    fn schedule_next_refuses_dates_past_year_9999() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Forever".into(),
+            Priority::Low,
+            Some("9999-12-31".into()),
+        );
+        store.todos[0].recurrence = Some(Recurrence::Daily);
+        assert!(mark_done(&mut store, 1));
+
+        assert!(schedule_next(&mut store, 1, date("2026-03-03")).is_err());
+        assert_eq!(store.todos.len(), 1);
+        assert_eq!(store.next_id, 2);
+    }
+
+    #[test]
+    fn completing_recurring_todo_schedules_next_and_keeps_original() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Water plants".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        store.todos[0].recurrence = Some(Recurrence::Weekly);
+        store.todos[0].tags = vec!["home".into()];
+        store.todos[0].note = Some("Use rainwater".into());
+        store.todos[0].silent = true;
+
+        assert!(mark_done(&mut store, 1));
+        let next = schedule_next(&mut store, 1, date("2026-03-03"));
+
+        assert_eq!(next, Ok(Some(2)));
+        assert!(store.todos[0].completed);
+        let next = &store.todos[1];
+        assert_eq!(next.title, "Water plants");
+        assert!(!next.completed);
+        assert_eq!(next.due_date.as_deref(), Some("2026-03-08"));
+        assert_eq!(next.recurrence, Some(Recurrence::Weekly));
+        assert_eq!(next.tags, vec!["home"]);
+        assert_eq!(next.note.as_deref(), Some("Use rainwater"));
+        assert!(next.silent);
+    }
+
+    #[test]
+    fn schedule_next_uses_today_without_due_date() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Stretch".into(), Priority::Low, None);
+        store.todos[0].recurrence = Some(Recurrence::Daily);
+        schedule_next(&mut store, 1, date("2026-03-03")).unwrap();
+        assert_eq!(store.todos[1].due_date.as_deref(), Some("2026-03-04"));
+    }
+
+    #[test]
+    fn schedule_next_ignores_one_off_todos() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Once".into(), Priority::Low, None);
+        assert_eq!(schedule_next(&mut store, 1, date("2026-03-03")), Ok(None));
+        assert_eq!(store.todos.len(), 1);
+    }
+
+    // -- mark_undone tests --
+
+    #[test]
+    fn mark_undone_reopens_done_todo() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        mark_done(&mut store, 1);
+        assert!(mark_undone(&mut store, 1));
+        assert!(!store.todos[0].completed);
+        assert_eq!(store.todos[0].completed_at, None);
+    }
+
+    #[test]
+    fn mark_undone_idempotent() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        assert!(mark_undone(&mut store, 1));
+        assert!(mark_undone(&mut store, 1));
+        assert!(!store.todos[0].completed);
+    }
+
+    #[test]
+    fn mark_undone_nonexistent_returns_false() {
+        let mut store = empty_store();
+        assert!(!mark_undone(&mut store, 99));
+    }
+
+    // -- comment tests --
+
+    #[test]
+    fn comments_accumulate_with_timestamps_in_order() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        assert!(add_comment(
+            &mut store,
+            1,
+            "Called the vendor",
+            "2026-03-01 09:15"
+        ));
+        assert!(add_comment(
+            &mut store,
+            1,
+            "Waiting on quote",
+            "2026-03-02 14:00"
+        ));
+        assert_eq!(
+            store.todos[0].comments,
+            vec![
+                "2026-03-01 09:15 Called the vendor",
+                "2026-03-02 14:00 Waiting on quote",
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_on_missing_todo_returns_false() {
+        let mut store = empty_store();
+        assert!(!add_comment(&mut store, 1, "Hello", "2026-03-01 09:15"));
+    }
+
+    // -- streak tests --
+
+    #[test]
+    fn streak_increments_on_consecutive_day() {
+        let today = date("2026-03-02");
+        assert_eq!(update_streak(3, Some(date("2026-03-01")), today), 4);
+    }
+
+    #[test]
+    fn streak_unchanged_on_same_day() {
+        let today = date("2026-03-02");
+        assert_eq!(update_streak(3, Some(today), today), 3);
+    }
+
+    #[test]
+    fn streak_resets_after_gap() {
+        let today = date("2026-03-05");
+        assert_eq!(update_streak(3, Some(date("2026-03-02")), today), 1);
+        assert_eq!(update_streak(0, None, today), 1);
+    }
+
+    #[test]
+    fn active_streak_lapses_after_missed_day() {
+        let last = Some(date("2026-03-01"));
+        assert_eq!(active_streak(4, last, date("2026-03-02")), 4);
+        assert_eq!(active_streak(4, last, date("2026-03-03")), 0);
+    }
+
+    #[test]
+    fn record_completion_updates_store() {
+        let mut store = empty_store();
+        record_completion(&mut store, date("2026-03-01"));
+        record_completion(&mut store, date("2026-03-02"));
+        assert_eq!(store.completion_streak, 2);
+        assert_eq!(store.last_completion_date.as_deref(), Some("2026-03-02"));
+    }
+
+    // -- edit_todo tests --
+
+    #[test]
+    fn edit_todo_applies_only_given_fields() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Tpyo".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        assert!(edit_todo(&mut store, 1, Some("Typo".into()), None, None));
+        let todo = &store.todos[0];
+        assert_eq!(todo.title, "Typo");
+        assert_eq!(todo.priority, Priority::Low);
+        assert_eq!(todo.due_date.as_deref(), Some("2026-03-01"));
+
+        assert!(edit_todo(
+            &mut store,
+            1,
+            None,
+            Some(Priority::High),
+            Some("2026-04-01".into())
+        ));
+        let todo = &store.todos[0];
+        assert_eq!(todo.title, "Typo");
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.due_date.as_deref(), Some("2026-04-01"));
+    }
+
+    #[test]
+    fn edit_todo_empty_due_clears_date() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Task".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        assert!(edit_todo(&mut store, 1, None, None, Some(String::new())));
+        assert_eq!(store.todos[0].due_date, None);
+    }
+
+    #[test]
+    fn edit_todo_keeps_id_and_created_at() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-01-01".into();
+        edit_todo(&mut store, 1, Some("Renamed".into()), None, None);
+        assert_eq!(store.todos[0].id, 1);
+        assert_eq!(store.todos[0].created_at, "2026-01-01");
+    }
+
+    #[test]
+    fn edit_rejects_empty_title_flag() {
+        for title in ["", "   "] {
+            let cli = Cli::try_parse_from(["todo-cli", "edit", "1", "--title", title]);
+            assert!(cli.is_err());
+        }
+        assert!(Cli::try_parse_from(["todo-cli", "edit", "1", "--title", "Ok"]).is_ok());
+    }
+
+    #[test]
+    fn edit_todo_nonexistent_returns_false() {
+        let mut store = empty_store();
+        assert!(!edit_todo(&mut store, 99, Some("Nope".into()), None, None));
+    }
+
+    #[test]
+    fn apply_field_edit_sets_chosen_field() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Draft".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        let todo = &mut store.todos[0];
+
+        apply_field_edit(todo, "priority", "1").unwrap();
+        apply_field_edit(todo, "Title", " Final draft ").unwrap();
+        apply_field_edit(todo, "assignee", "sam").unwrap();
+        apply_field_edit(todo, "due", "").unwrap();
+
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.title, "Final draft");
+        assert_eq!(todo.assignee.as_deref(), Some("sam"));
+        assert_eq!(todo.due_date, None);
+    }
+
+    #[test]
+    fn apply_field_edit_rejects_bad_input() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        let todo = &mut store.todos[0];
+
+        assert!(apply_field_edit(todo, "colour", "red").is_err());
+        assert!(apply_field_edit(todo, "title", "  ").is_err());
+        assert!(apply_field_edit(todo, "due", "2026-13-01").is_err());
+        assert_eq!(todo.title, "Task");
+        assert_eq!(todo.due_date, None);
+    }
+
+    // -- digest tests --
+
+    #[test]
+    fn week_bounds_span_monday_to_sunday() {
+        assert_eq!(
+            week_bounds(date("2026-03-04")),
+            (date("2026-03-02"), date("2026-03-08"))
+        );
+        assert_eq!(
+            week_bounds(date("2026-03-02")),
+            (date("2026-03-02"), date("2026-03-08"))
+        );
+        assert_eq!(
+            week_bounds(date("2026-03-08")),
+            (date("2026-03-02"), date("2026-03-08"))
+        );
+    }
+
+    #[test]
+    fn month_bounds_cover_whole_month() {
+        assert_eq!(
+            month_bounds(date("2026-02-14")),
+            (date("2026-02-01"), date("2026-02-28"))
+        );
+        assert_eq!(
+            month_bounds(date("2026-12-31")),
+            (date("2026-12-01"), date("2026-12-31"))
+        );
+    }
+
+    #[test]
+    fn intensity_grows_with_completion_count() {
+        let shades: String = [0, 1, 2, 3, 4, 5, 9].into_iter().map(intensity).collect();
+        assert_eq!(shades, ".░▒▓▓██");
+    }
+
+    #[test]
+    fn streak_calendar_shades_days_by_completions() {
+        let mut store = empty_store();
+        let completions = [
+            "2026-03-02",
+            "2026-03-03",
+            "2026-03-03",
+            "2026-03-04",
+            "2026-03-04",
+            "2026-03-04",
+            "2026-03-05",
+            "2026-03-05",
+            "2026-03-05",
+            "2026-03-05",
+            "2026-03-05",
+            "2026-04-01",
+        ];
+        for (i, day) in completions.iter().enumerate() {
+            let id = add_todo(&mut store, format!("Task {}", i), Priority::Low, None);
+            mark_done(&mut store, id);
+            store.todos[i].completed_at = Some(day.to_string());
+        }
+
+        let (start, end) = month_bounds(date("2026-03-15"));
+        let days = completions_by_day(&store, start, end);
+        assert_eq!(days.len(), 31);
+        assert_eq!(days[1], (date("2026-03-02"), 1));
+        assert_eq!(days[4], (date("2026-03-05"), 5));
+        assert_eq!(days.iter().map(|(_, n)| n).sum::<usize>(), 11);
+
+        let calendar = render_streak_calendar(&days);
+        let lines: Vec<&str> = calendar.lines().collect();
+        assert_eq!(lines[0], "March 2026");
+        // March 2026 starts on a Sunday, so its first week column holds
+        // only the 1st.
+        assert_eq!(lines[1], "Mon   ░ . . . .");
+        assert_eq!(lines[2], "Tue   ▒ . . . .");
+        assert_eq!(lines[3], "Wed   ▓ . . .");
+        assert_eq!(lines[4], "Thu   █ . . .");
+        assert_eq!(lines[7], "Sun . . . . .");
+        assert_eq!(lines[8], "Less . ░ ▒ ▓ █ More");
+    }
+
+    #[test]
+    fn completions_group_under_their_day() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Tue A".into(), Priority::High, None);
+        add_todo(&mut store, "Mon".into(), Priority::Low, None);
+        add_todo(&mut store, "Tue B".into(), Priority::Medium, None);
+        add_todo(&mut store, "Last week".into(), Priority::Low, None);
+        add_todo(&mut store, "Pending".into(), Priority::Low, None);
+        for (id, day) in [
+            (1, "2026-03-03"),
+            (2, "2026-03-02"),
+            (3, "2026-03-03"),
+            (4, "2026-02-27"),
+        ] {
+            mark_done(&mut store, id);
+            store.todos[id as usize - 1].completed_at = Some(day.into());
+        }
+
+        let (start, end) = week_bounds(date("2026-03-04"));
+        let days = completions_by_day_between(&store, start, end);
+
+        let summary: Vec<(NaiveDate, Vec<&str>)> = days
+            .iter()
+            .map(|(d, todos)| (*d, todos.iter().map(|t| t.title.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (date("2026-03-02"), vec!["Mon"]),
+                (date("2026-03-03"), vec!["Tue A", "Tue B"]),
+            ]
+        );
+    }
+
+    // -- remove_todo tests --
+
+    #[test]
+    fn remove_existing_todo() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        assert!(remove_todo(&mut store, 1));
+        assert!(store.todos.is_empty());
+    }
+
+    #[test]
+    fn remove_nonexistent_returns_false() {
+        let mut store = empty_store();
+        assert!(!remove_todo(&mut store, 99));
+    }
+
+    #[test]
+    fn remove_only_target_todo() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Keep".into(), Priority::Low, None);
+        add_todo(&mut store, "Remove".into(), Priority::High, None);
+        assert!(remove_todo(&mut store, 2));
+        assert_eq!(store.todos.len(), 1);
+        assert_eq!(store.todos[0].title, "Keep");
+    }
+
+    #[test]
+    fn remove_created_before_only_prunes_older_todos() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Ancient".into(), Priority::Low, None);
+        add_todo(&mut store, "Old done".into(), Priority::Low, None);
+        add_todo(&mut store, "Cutoff day".into(), Priority::Low, None);
+        add_todo(&mut store, "Recent".into(), Priority::Low, None);
+        store.todos[0].created_at = "2024-06-01".into();
+        store.todos[1].created_at = "2024-12-31".into();
+        store.todos[2].created_at = "2025-01-01".into();
+        store.todos[3].created_at = "2026-02-01".into();
+        mark_done(&mut store, 2);
+
+        let removed = remove_created_before(&mut store, date("2025-01-01"));
+
+        assert_eq!(removed, 2);
+        let ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![3, 4]);
+    }
+
+    // -- clear_done tests --
+
+    fn mixed_store() -> TodoStore {
+        let mut store = empty_store();
+        for title in ["Done A", "Pending B", "Done C", "Pending D"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        mark_done(&mut store, 1);
+        mark_done(&mut store, 3);
+        store
+    }
+
+    #[test]
+    fn clear_done_returns_removed_count() {
+        let mut store = mixed_store();
+        assert_eq!(clear_done(&mut store), 2);
+        assert_eq!(clear_done(&mut store), 0);
+    }
+
+    #[test]
+    fn clear_done_keeps_pending_todos() {
+        let mut store = mixed_store();
+        clear_done(&mut store);
+        let ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 4]);
+        assert!(store.todos.iter().all(|t| !t.completed));
+    }
+
+    #[test]
+    fn clear_done_leaves_next_id_unchanged() {
+        let mut store = mixed_store();
+        clear_done(&mut store);
+        assert_eq!(store.next_id, 5);
+        assert_eq!(add_todo(&mut store, "New".into(), Priority::Low, None), 5);
+    }
+
+    // -- renumber tests --
+
+    #[test]
+    fn renumber_compacts_sparse_ids_in_order() {
+        let mut store = empty_store();
+        for title in ["A", "B", "C", "D", "E"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        remove_todo(&mut store, 2);
+        remove_todo(&mut store, 3);
+        mark_done(&mut store, 4);
+
+        assert_eq!(renumber(&mut store), vec![(4, 2), (5, 3)]);
+
+        let ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+        let titles: Vec<&str> = store.todos.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(titles, vec!["A", "D", "E"]);
+        assert!(store.todos[1].completed);
+        assert_eq!(store.next_id, 4);
+    }
+
+    #[test]
+    fn renumber_empty_store_resets_next_id() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Gone".into(), Priority::Low, None);
+        remove_todo(&mut store, 1);
+        assert_eq!(store.next_id, 2);
+        renumber(&mut store);
+        assert_eq!(store.next_id, 1);
+    }
+
+    // -- escalate_stale tests --
+
+    #[test]
+    fn escalate_stale_bumps_old_low_todo_once() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Stale".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-01-20".into();
+        let today = date("2026-03-01");
+
+        assert_eq!(escalate_stale(&mut store, today, 30), 1);
+        assert_eq!(store.todos[0].priority, Priority::Medium);
+        assert!(store.todos[0].escalated);
+
+        assert_eq!(escalate_stale(&mut store, today, 30), 0);
+        assert_eq!(store.todos[0].priority, Priority::Medium);
+    }
+
+    #[test]
+    fn escalate_stale_skips_recent_done_and_high() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Recent".into(), Priority::Low, None);
+        add_todo(&mut store, "Done".into(), Priority::Low, None);
+        add_todo(&mut store, "High".into(), Priority::High, None);
+        store.todos[0].created_at = "2026-02-20".into();
+        store.todos[1].created_at = "2025-01-01".into();
+        store.todos[2].created_at = "2025-01-01".into();
+        mark_done(&mut store, 2);
+
+        assert_eq!(escalate_stale(&mut store, date("2026-03-01"), 30), 0);
+        assert_eq!(store.todos[0].priority, Priority::Low);
+        assert_eq!(store.todos[1].priority, Priority::Low);
+        assert!(!store.todos[2].escalated);
+    }
+
+    #[test]
+    fn escalate_after_rejects_zero_and_negative_days() {
+        assert!(Cli::try_parse_from(["todo-cli", "--escalate-after=-5", "list"]).is_err());
+        assert!(Cli::try_parse_from(["todo-cli", "--escalate-after", "0", "list"]).is_err());
+        let cli = Cli::try_parse_from(["todo-cli", "--escalate-after", "30", "list"]).unwrap();
+        assert_eq!(cli.escalate_after, Some(30));
+    }
+
+    // -- filter_todos tests --
+
+    #[test]
+    fn filter_pending_excludes_done() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Pending".into(), Priority::Low, None);
+        add_todo(&mut store, "Done".into(), Priority::Low, None);
+        mark_done(&mut store, 2);
+        let result = filter_todos(&store, &ListFilter::Pending);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Pending");
+    }
+
+    #[test]
+    fn filter_done_excludes_pending() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Pending".into(), Priority::Low, None);
+        add_todo(&mut store, "Done".into(), Priority::Low, None);
+        mark_done(&mut store, 2);
+        let result = filter_todos(&store, &ListFilter::Done);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Done");
+    }
+
+    #[test]
+    fn filter_all_returns_everything() {
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(&mut store, "B".into(), Priority::High, None);
+        mark_done(&mut store, 2);
+        let result = filter_todos(&store, &ListFilter::All);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn contains_composes_with_filter_all() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Buy milk".into(), Priority::Low, None);
+        add_todo(&mut store, "Buy bread".into(), Priority::Low, None);
+        add_todo(&mut store, "Oat MILK latte".into(), Priority::High, None);
+        mark_done(&mut store, 3);
+        let mut result = filter_todos(&store, &ListFilter::All);
+        result.retain(|t| title_contains(t, "milk"));
+        let titles: Vec<&str> = result.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Buy milk", "Oat MILK latte"]);
+    }
+
+    #[test]
+    fn contains_respects_pending_filter() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Buy milk".into(), Priority::Low, None);
+        add_todo(&mut store, "Oat milk".into(), Priority::Low, None);
+        mark_done(&mut store, 2);
+        let mut result = filter_todos(&store, &ListFilter::Pending);
+        result.retain(|t| title_contains(t, "milk"));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Buy milk");
+    }
+
+    #[test]
+    fn assignee_filter_returns_only_matching() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Dishes".into(), Priority::Low, None);
+        add_todo(&mut store, "Laundry".into(), Priority::Low, None);
+        add_todo(&mut store, "Taxes".into(), Priority::High, None);
+        find_todo_mut(&mut store, 1).unwrap().assignee = Some("Sam".into());
+        find_todo_mut(&mut store, 2).unwrap().assignee = Some("Alex".into());
+        let mut result = filter_todos(&store, &ListFilter::All);
+        result.retain(|t| assigned_to(t, "sam"));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].title, "Dishes");
+    }
+
+    #[test]
+    fn store_without_newer_fields_loads() {
+        let path = temp_path("no-assignee");
+        fs::write(
+            &path,
+            r#"{"next_id":2,"todos":[{"id":1,"title":"Old","completed":false,"priority":"low","due_date":null,"created_at":"2026-01-01"}]}"#,
+        )
+        .unwrap();
+        let store = load_store(&path).unwrap();
+        assert_eq!(store.todos.len(), 1);
+        assert_eq!(store.todos[0].assignee, None);
+        assert_eq!(store.todos[0].completed_at, None);
+        fs::remove_file(&path).ok();
+    }
+
+    // -- is_overdue tests --
+
+    #[test]
+    fn is_overdue_when_due_before_today() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::Low,
+            Some("2026-02-28".into()),
+        );
+        assert!(is_overdue(&store.todos[0], date("2026-03-01")));
+    }
+
+    #[test]
+    fn is_overdue_false_when_due_today() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Today".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        assert!(!is_overdue(&store.todos[0], date("2026-03-01")));
+    }
+
+    #[test]
+    fn is_overdue_false_when_due_in_future() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Later".into(),
+            Priority::Low,
+            Some("2026-03-02".into()),
+        );
+        assert!(!is_overdue(&store.todos[0], date("2026-03-01")));
+    }
+
+    #[test]
+    fn is_overdue_false_without_valid_date() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Undated".into(), Priority::Low, None);
+        add_todo(
+            &mut store,
+            "Garbage".into(),
+            Priority::Low,
+            Some("notadate".into()),
+        );
+        assert!(!is_overdue(&store.todos[0], date("2026-03-01")));
+        assert!(!is_overdue(&store.todos[1], date("2026-03-01")));
+    }
+
+    #[test]
+    fn is_overdue_false_when_completed() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Done late".into(),
+            Priority::Low,
+            Some("2026-02-01".into()),
+        );
+        mark_done(&mut store, 1);
+        assert!(!is_overdue(&store.todos[0], date("2026-03-01")));
+    }
+
+    #[test]
+    fn silent_todo_is_never_overdue_or_due_soon() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Reference".into(),
+            Priority::Low,
+            Some("2020-01-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::Low,
+            Some("2020-01-01".into()),
+        );
+        store.todos[0].silent = true;
+
+        let overdue: Vec<u32> = filter_todos(&store, &ListFilter::Overdue)
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(overdue, vec![2]);
+        assert_eq!(filter_todos(&store, &ListFilter::Pending).len(), 2);
+
+        store.todos[0].due_date = Some("2026-03-02".into());
+        assert!(!due_soon(&store.todos[0], date("2026-03-01"), 7));
+    }
+
+    #[test]
+    fn overdue_days_threshold_keeps_only_severe_todos() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Ten".into(),
+            Priority::Low,
+            Some("2026-02-19".into()),
+        );
+        add_todo(
+            &mut store,
+            "Three".into(),
+            Priority::Low,
+            Some("2026-02-26".into()),
+        );
+        let today = date("2026-03-01");
+        assert_eq!(days_overdue(&store.todos[0], today), Some(10));
+        assert_eq!(days_overdue(&store.todos[1], today), Some(3));
+
+        let todos: Vec<&Todo> = store
+            .todos
+            .iter()
+            .filter(|t| days_overdue(t, today).is_some_and(|days| days >= 7))
+            .collect();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "Ten");
+    }
+
+    #[test]
+    fn days_overdue_none_when_not_overdue() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Today".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "Done".into(),
+            Priority::Low,
+            Some("2026-01-01".into()),
+        );
+        mark_done(&mut store, 2);
+        assert_eq!(days_overdue(&store.todos[0], date("2026-03-01")), None);
+        assert_eq!(days_overdue(&store.todos[1], date("2026-03-01")), None);
+    }
+
+    #[test]
+    fn overdue_rows_are_marked() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::Low,
+            Some("2026-02-28".into()),
+        );
+        add_todo(
+            &mut store,
+            "Fine".into(),
+            Priority::Low,
+            Some("2026-03-05".into()),
+        );
+        let today = date("2026-03-01");
+        assert_eq!(
+            table_row(&store.todos[0], today, &TableStyle::default())[3],
+            "2026-02-28 (OVERDUE)"
+        );
+        assert_eq!(
+            table_row(&store.todos[1], today, &TableStyle::default())[3],
+            "2026-03-05"
+        );
+    }
+
+    // -- search_todos tests --
+
+    fn search_store() -> TodoStore {
+        let mut store = empty_store();
+        add_todo(&mut store, "Pay Invoice".into(), Priority::High, None);
+        add_todo(&mut store, "File invoice copy".into(), Priority::Low, None);
+        add_todo(&mut store, "Walk dog".into(), Priority::Low, None);
+        mark_done(&mut store, 2);
+        store
+    }
+
+    #[test]
+    fn search_is_case_insensitive_by_default() {
+        let store = search_store();
+        let ids: Vec<u32> = search_todos(&store, "INVOICE", false)
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn search_case_sensitive_matches_exactly() {
+        let store = search_store();
+        let ids: Vec<u32> = search_todos(&store, "Invoice", true)
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[test]
+    fn search_with_no_match_is_empty() {
+        let store = search_store();
+        assert!(search_todos(&store, "groceries", false).is_empty());
+    }
+
+    // -- duplicate_todos tests --
+
+    #[test]
+    fn duplicates_lists_only_repeated_titles_grouped() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Report".into(), Priority::Low, None);
+        add_todo(&mut store, "Unique".into(), Priority::Low, None);
+        add_todo(&mut store, "Groceries".into(), Priority::Low, None);
+        add_todo(&mut store, "report".into(), Priority::High, None);
+        let todos = filter_todos(&store, &ListFilter::All);
+        let ids: Vec<u32> = duplicate_todos(&todos).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 4]);
+    }
+
+    #[test]
+    fn duplicates_groups_each_title_together() {
+        let mut store = empty_store();
+        for title in ["A", "B", "a", "b", "C"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        let todos = filter_todos(&store, &ListFilter::All);
+        let ids: Vec<u32> = duplicate_todos(&todos).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn find_duplicate_blocks_pending_match() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Buy milk".into(), Priority::Low, None);
+        add_todo(&mut store, "Call mom".into(), Priority::Low, None);
+        assert_eq!(find_duplicate(&store, "Call mom").map(|t| t.id), Some(2));
+        assert!(find_duplicate(&store, "Call dad").is_none());
+    }
+
+    #[test]
+    fn find_duplicate_ignores_completed_match() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Buy milk".into(), Priority::Low, None);
+        mark_done(&mut store, 1);
+        assert!(find_duplicate(&store, "Buy milk").is_none());
+    }
+
+    #[test]
+    fn find_duplicate_ignores_case() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Buy Milk".into(), Priority::Low, None);
+        assert_eq!(find_duplicate(&store, "buy MILK").map(|t| t.id), Some(1));
+    }
+
+    #[test]
+    fn repeated_title_checks_within_batch_ignoring_case() {
+        let titles = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(repeated_title(&titles(&["Q", "Q"])), Some("Q"));
+        assert_eq!(
+            repeated_title(&titles(&["Buy milk", "A", "buy MILK"])),
+            Some("buy MILK")
+        );
+        assert_eq!(repeated_title(&titles(&["A", "B"])), None);
+    }
+
+    // -- tag tests --
+
+    fn tagged_store() -> TodoStore {
+        let mut store = empty_store();
+        add_todo(&mut store, "Standup".into(), Priority::Low, None);
+        add_todo(&mut store, "Dishes".into(), Priority::Low, None);
+        add_todo(&mut store, "Deploy".into(), Priority::High, None);
+        store.todos[0].tags = vec!["work".into(), "daily".into()];
+        store.todos[1].tags = vec!["home".into()];
+        store.todos[2].tags = vec!["work".into()];
+        mark_done(&mut store, 3);
+        store
+    }
+
+    #[test]
+    fn add_accepts_multiple_tags() {
+        let cli = Cli::try_parse_from([
+            "todo-cli", "add", "Standup", "--tag", "work", "--tag", "daily",
+        ])
+        .unwrap();
+        let Commands::Add { tags, .. } = cli.command else {
+            unreachable!()
+        };
+        assert_eq!(tags, vec!["work", "daily"]);
+    }
+
+    #[test]
+    fn tag_filter_keeps_only_tagged() {
+        let store = tagged_store();
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        todos.retain(|t| has_tag(t, "work"));
+        let ids: Vec<u32> = todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn tag_filter_composes_with_status() {
+        let store = tagged_store();
+        let mut todos = filter_todos(&store, &ListFilter::Pending);
+        todos.retain(|t| has_tag(t, "work"));
+        let ids: Vec<u32> = todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[test]
+    fn tags_render_comma_separated() {
+        let store = tagged_store();
+        assert_eq!(
+            table_row(&store.todos[0], date("2026-03-01"), &TableStyle::default())[4],
+            "work,daily"
+        );
+        let mut untagged = empty_store();
+        add_todo(&mut untagged, "Plain".into(), Priority::Low, None);
+        assert_eq!(
+            table_row(
+                &untagged.todos[0],
+                date("2026-03-01"),
+                &TableStyle::default()
+            )[4],
+            "-"
+        );
+    }
+
+    // -- due_soon tests --
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn due_soon_respects_window() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Soon".into(),
+            Priority::Low,
+            Some("2026-03-06".into()),
+        );
+        let today = date("2026-03-01");
+        assert!(due_soon(&store.todos[0], today, 7));
+        assert!(!due_soon(&store.todos[0], today, 3));
+    }
+
+    #[test]
+    fn due_soon_includes_today_and_last_day() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Today".into(),
+            Priority::Low,
+            Some("2026-03-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "Edge".into(),
+            Priority::Low,
+            Some("2026-03-08".into()),
+        );
+        let today = date("2026-03-01");
+        assert!(due_soon(&store.todos[0], today, 7));
+        assert!(due_soon(&store.todos[1], today, 7));
+    }
+
+    #[test]
+    fn due_soon_accepts_very_large_window() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Far".into(),
+            Priority::Low,
+            Some("9999-12-31".into()),
+        );
+        assert!(due_soon(&store.todos[0], date("2026-03-01"), u32::MAX));
+    }
+
+    #[test]
+    fn due_soon_excludes_past_undated_and_completed() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Past".into(),
+            Priority::Low,
+            Some("2026-02-27".into()),
+        );
+        add_todo(&mut store, "Undated".into(), Priority::Low, None);
+        add_todo(
+            &mut store,
+            "Done".into(),
+            Priority::Low,
+            Some("2026-03-02".into()),
+        );
+        mark_done(&mut store, 3);
+        let today = date("2026-03-01");
+        assert!(store.todos.iter().all(|t| !due_soon(t, today, 7)));
+    }
+
+    // -- relative date tests --
+
+    #[test]
+    fn parse_relative_days_units() {
+        assert_eq!(parse_relative_days("7d"), Ok(7));
+        assert_eq!(parse_relative_days("1w"), Ok(7));
+        assert_eq!(parse_relative_days("2m"), Ok(60));
+        assert!(parse_relative_days("").is_err());
+        assert!(parse_relative_days("d").is_err());
+        assert!(parse_relative_days("7y").is_err());
+        assert!(parse_relative_days("-1d").is_err());
+        assert!(parse_relative_days("7é").is_err());
+    }
+
+    #[test]
+    fn parse_relative_days_rejects_overflowing_spans() {
+        assert!(parse_relative_days("9223372036854775807w").is_err());
+        assert!(parse_relative_days("999999999999999d").is_err());
+        assert_eq!(parse_relative_days("36500d"), Ok(36_500));
+        assert!(parse_relative_days("36501d").is_err());
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--since", "9223372036854775807w"]);
+        assert!(cli.is_err());
+    }
+
+    #[test]
+    fn created_since_without_lower_bound_keeps_everything() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Ancient".into(), Priority::Low, None);
+        store.todos[0].created_at = "0001-01-01".into();
+        assert!(created_since(
+            &store.todos[0],
+            NaiveDate::MIN,
+            MAX_RELATIVE_DAYS
+        ));
+    }
+
+    #[test]
+    fn created_since_uses_relative_window() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Recent".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-02-26".into();
+        let today = date("2026-03-01");
+        assert!(created_since(
+            &store.todos[0],
+            today,
+            parse_relative_days("7d").unwrap()
+        ));
+        assert!(!created_since(
+            &store.todos[0],
+            today,
+            parse_relative_days("2d").unwrap()
+        ));
+    }
+
+    // -- query tests --
+
+    fn query_titles(store: &TodoStore, query: &str) -> Vec<String> {
+        let query = parse_query(query).unwrap();
+        store
+            .todos
+            .iter()
+            .filter(|t| query.matches(t))
+            .map(|t| t.title.clone())
+            .collect()
+    }
+
+    fn query_store() -> TodoStore {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Taxes".into(),
+            Priority::High,
+            Some("2026-03-15".into()),
+        );
+        add_todo(
+            &mut store,
+            "Report".into(),
+            Priority::High,
+            Some("2026-05-01".into()),
+        );
+        add_todo(&mut store, "Buy milk".into(), Priority::Low, None);
+        add_todo(
+            &mut store,
+            "Filed".into(),
+            Priority::High,
+            Some("2026-02-01".into()),
+        );
+        mark_done(&mut store, 4);
+        store
+    }
+
+    #[test]
+    fn query_without_filter_starts_from_all_todos() {
+        let store = query_store();
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--query", "done"]).unwrap();
+        let Commands::List { filter, query, .. } = cli.command else {
+            panic!("expected list");
+        };
+        let query = parse_query(query.as_deref().unwrap()).unwrap();
+        let filter = list_filter(filter, true);
+        let titles: Vec<&str> = filter_todos(&store, &filter)
+            .into_iter()
+            .filter(|t| query.matches(t))
+            .map(|t| t.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Filed"]);
+        assert_eq!(list_filter(None, false), ListFilter::Pending);
+        assert_eq!(
+            list_filter(Some(ListFilter::Pending), true),
+            ListFilter::Pending
+        );
+    }
+
+    #[test]
+    fn query_combines_terms_with_and_not() {
+        let store = query_store();
+        assert_eq!(
+            query_titles(
+                &store,
+                "priority:high AND due-before:2026-04-01 AND NOT done"
+            ),
+            vec!["Taxes"]
+        );
+    }
+
+    #[test]
+    fn query_and_binds_tighter_than_or() {
+        let store = query_store();
+        assert_eq!(
+            query_titles(&store, "contains:milk OR priority:high AND done"),
+            vec!["Buy milk", "Filed"]
+        );
+        assert_eq!(
+            query_titles(&store, "(contains:milk OR priority:high) AND NOT done"),
+            vec!["Taxes", "Report", "Buy milk"]
+        );
+    }
+
+    #[test]
+    fn query_not_applies_to_single_term() {
+        let store = query_store();
+        assert_eq!(
+            query_titles(&store, "NOT done AND due-after:2026-04-01"),
+            vec!["Report"]
+        );
+        assert_eq!(
+            query_titles(&store, "not (done or priority:high)"),
+            vec!["Buy milk"]
+        );
+    }
+
+    #[test]
+    fn query_matches_tags() {
+        let mut store = query_store();
+        store.todos[0].tags = vec!["finance".into()];
+        store.todos[3].tags = vec!["finance".into()];
+        assert_eq!(
+            query_titles(&store, "tag:finance AND NOT done"),
+            vec!["Taxes"]
+        );
+    }
+
+    #[test]
+    fn query_supports_quoted_values() {
+        let store = query_store();
+        assert_eq!(
+            query_titles(&store, "contains:\"buy milk\""),
+            vec!["Buy milk"]
+        );
+    }
+
+    #[test]
+    fn query_rejects_malformed_input() {
+        assert!(parse_query("").is_err());
+        assert!(parse_query("priority:urgent").is_err());
+        assert!(parse_query("due-before:tomorrow").is_err());
+        assert!(parse_query("(done").is_err());
+        assert!(parse_query("done AND").is_err());
+        assert!(parse_query("done done").is_err());
+        assert!(parse_query("color:red").is_err());
+    }
+
+    // -- next_page tests --
+
+    #[test]
+    fn successive_pages_are_disjoint() {
+        let mut store = empty_store();
+        for i in 1..=5 {
+            add_todo(&mut store, format!("Task {}", i), Priority::Low, None);
+        }
+        let todos = filter_todos(&store, &ListFilter::All);
+
+        let first = next_page(store.list_cursor, todos.len(), 2);
+        let first_ids: Vec<u32> = todos[first.clone()].iter().map(|t| t.id).collect();
+        let second = next_page(first.end, todos.len(), 2);
+        let second_ids: Vec<u32> = todos[second.clone()].iter().map(|t| t.id).collect();
+
+        assert_eq!(first_ids, vec![1, 2]);
+        assert_eq!(second_ids, vec![3, 4]);
+    }
+
+    #[test]
+    fn next_conflicts_with_format() {
+        for format in ["json", "count-table", "table"] {
+            let args = ["todo-cli", "list", "--next", "--format", format];
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+        assert!(Cli::try_parse_from(["todo-cli", "list", "--next"]).is_ok());
+    }
+
+    #[test]
+    fn next_conflicts_with_limit() {
+        assert!(Cli::try_parse_from(["todo-cli", "list", "--next", "--limit", "5"]).is_err());
+        assert!(Cli::try_parse_from(["todo-cli", "list", "--limit", "5"]).is_ok());
+    }
+
+    #[test]
+    fn page_size_must_be_positive() {
+        let args = |size| ["todo-cli", "list", "--next", "--page-size", size];
+        assert!(Cli::try_parse_from(args("0")).is_err());
+        assert!(Cli::try_parse_from(args("-1")).is_err());
+        assert!(Cli::try_parse_from(args("1")).is_ok());
+    }
+
+    #[test]
+    fn next_page_stops_at_end() {
+        assert_eq!(next_page(4, 5, 2), 4..5);
+        assert_eq!(next_page(5, 5, 2), 5..5);
+        assert_eq!(next_page(9, 5, 2), 5..5);
+    }
+
+    #[test]
+    fn next_page_with_huge_page_size_does_not_overflow() {
+        assert_eq!(next_page(3, 5, usize::MAX), 3..5);
+        assert_eq!(next_page(5, 5, usize::MAX), 5..5);
+    }
+
+    // -- cap_results tests --
+
+    #[test]
+    fn max_results_shows_cap_and_note() {
+        let mut store = empty_store();
+        for title in ["Report A", "Report B", "Report C"] {
+            add_todo(&mut store, title.into(), Priority::Low, None);
+        }
+        let mut todos = search_todos(&store, "report", false);
+        let hidden = cap_results(&mut todos, Some(2));
+        let ids: Vec<u32> = todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(hidden_note(hidden).as_deref(), Some("(1 more not shown)"));
+    }
+
+    fn limited_ids(count: u32, limit: Option<usize>) -> Vec<u32> {
+        let mut store = empty_store();
+        for i in 0..count {
+            add_todo(&mut store, format!("Task {}", i), Priority::Low, None);
+        }
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        sort_todos(&mut todos, &SortKey::Id);
+        cap_results(&mut todos, limit);
+        todos.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn list_limit_below_count_keeps_first_n() {
+        assert_eq!(limited_ids(4, Some(2)), vec![1, 2]);
+    }
+
+    #[test]
+    fn list_limit_at_or_above_count_keeps_all() {
+        assert_eq!(limited_ids(3, Some(3)), vec![1, 2, 3]);
+        assert_eq!(limited_ids(3, Some(10)), vec![1, 2, 3]);
+        assert_eq!(limited_ids(3, None), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn list_limit_zero_shows_nothing() {
+        assert!(limited_ids(3, Some(0)).is_empty());
+    }
+
+    #[test]
+    fn max_results_default_is_unlimited() {
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(&mut store, "B".into(), Priority::Low, None);
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        assert_eq!(cap_results(&mut todos, None), 0);
+        assert_eq!(todos.len(), 2);
+        assert_eq!(hidden_note(0), None);
+    }
+
+    // -- sort_todos tests --
+
+    fn created_store() -> TodoStore {
+        let mut store = empty_store();
+        add_todo(&mut store, "Middle".into(), Priority::Low, None);
+        add_todo(&mut store, "Newest".into(), Priority::Low, None);
+        add_todo(&mut store, "Oldest".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-02-01".into();
+        store.todos[1].created_at = "2026-03-01".into();
+        store.todos[2].created_at = "2026-01-01".into();
+        store
+    }
+
+    #[test]
+    fn newest_puts_most_recent_first() {
+        let store = created_store();
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        sort_todos(&mut todos, &SortKey::Created);
+        todos.reverse();
+        let titles: Vec<&str> = todos.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Newest", "Middle", "Oldest"]);
+    }
+
+    #[test]
+    fn oldest_puts_earliest_first() {
+        let store = created_store();
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        sort_todos(&mut todos, &SortKey::Created);
+        let titles: Vec<&str> = todos.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Oldest", "Middle", "Newest"]);
+    }
+
+    #[test]
+    fn newest_and_oldest_conflict() {
+        let result = Cli::try_parse_from(["todo-cli", "list", "--newest", "--oldest"]);
+        assert!(result.is_err());
+        let result = Cli::try_parse_from(["todo-cli", "list", "--newest", "--sort", "due"]);
+        assert!(result.is_err());
+        let result = Cli::try_parse_from(["todo-cli", "list", "--oldest", "--sort", "id"]);
+        assert!(result.is_err());
+    }
+
+    fn sort_store() -> TodoStore {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Low later".into(),
+            Priority::Low,
+            Some("2026-05-01".into()),
+        );
+        add_todo(&mut store, "High undated".into(), Priority::High, None);
+        add_todo(
+            &mut store,
+            "Medium soon".into(),
+            Priority::Medium,
+            Some("2026-03-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "High soon".into(),
+            Priority::High,
+            Some("2026-04-01".into()),
+        );
+        store
+    }
+
+    fn sorted_ids(store: &TodoStore, key: SortKey) -> Vec<u32> {
+        let mut todos = filter_todos(store, &ListFilter::All);
+        todos.reverse();
+        sort_todos(&mut todos, &key);
+        todos.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn sort_by_id() {
+        assert_eq!(sorted_ids(&sort_store(), SortKey::Id), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sort_by_priority_high_first() {
+        assert_eq!(
+            sorted_ids(&sort_store(), SortKey::Priority),
+            vec![2, 4, 3, 1]
+        );
+    }
+
+    #[test]
+    fn sort_by_due_puts_undated_last() {
+        assert_eq!(sorted_ids(&sort_store(), SortKey::Due), vec![3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn sort_by_created_breaks_ties_by_id() {
+        let mut store = sort_store();
+        store.todos[2].created_at = "2025-12-01".into();
+        for id in [1, 2, 4] {
+            store.todos[id - 1].created_at = "2026-01-01".into();
+        }
+        assert_eq!(sorted_ids(&store, SortKey::Created), vec![3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn sort_age_lists_oldest_first_and_reverses() {
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--sort", "age", "--reverse"]).unwrap();
+        let Commands::List { sort, reverse, .. } = cli.command else {
+            panic!("expected list");
+        };
+        assert_eq!(sort, SortKey::Created);
+        assert!(reverse);
+
+        let store = created_store();
+        assert_eq!(sorted_ids(&store, sort), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn reverse_flips_sort_order() {
+        let store = sort_store();
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        sort_todos(&mut todos, &SortKey::Priority);
+        todos.reverse();
+        let ids: Vec<u32> = todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 3, 4, 2]);
+    }
+
+    // -- show tests --
+
+    #[test]
+    fn note_is_stored_and_survives_round_trip() {
+        let path = temp_path("note");
+        let mut store = empty_store();
+        add_todo(&mut store, "Plan trip".into(), Priority::Low, None);
+        store.todos[0].note = Some("Check visa rules first".into());
+        save_store(&store, &path, false).unwrap();
+        let loaded = load_store(&path).unwrap();
+        assert_eq!(
+            loaded.todos[0].note.as_deref(),
+            Some("Check visa rules first")
+        );
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn todo_details_lists_every_field() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Plan trip".into(),
+            Priority::High,
+            Some("2026-02-20".into()),
+        );
+        let todo = &mut store.todos[0];
+        todo.created_at = "2026-02-01".into();
+        todo.note = Some("Check visa rules first".into());
+        todo.comments = vec!["2026-02-02 10:00 Booked hotel".into()];
+
+        let details = todo_details(&store, 1, date("2026-03-01")).unwrap();
+
+        assert_eq!(
+            details,
+            [
+                "#1 Plan trip",
+                "  Status:    pending",
+                "  Priority:  high",
+                "  Due:       2026-02-20 (OVERDUE)",
+                "  Created:   2026-02-01",
+                "  Note:      Check visa rules first",
+                "  Comments:",
+                "    2026-02-02 10:00 Booked hotel",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn todo_details_missing_id_is_none() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Only".into(), Priority::Low, None);
+        assert!(todo_details(&store, 2, date("2026-03-01")).is_none());
+    }
+
+    // -- table layout tests --
+
+    #[test]
+    fn auto_widths_shrink_short_ids() {
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(
+            &mut store,
+            "B".into(),
+            Priority::High,
+            Some("2026-03-01".into()),
+        );
+        let rows: Vec<Vec<String>> = store
+            .todos
+            .iter()
+            .map(|t| table_row(t, date("2026-01-01"), &TableStyle::default()))
+            .collect();
+        let widths = auto_column_widths(&TABLE_HEADERS, &rows);
+        assert_eq!(widths, vec![2, 4, 8, 10, 4]);
+        assert!(widths[0] < FIXED_WIDTHS[0]);
+    }
+
+    #[test]
+    fn auto_widths_are_capped() {
+        let rows = vec![vec![
+            "1".to_string(),
+            "[ ]".to_string(),
+            "low".to_string(),
+            "x".repeat(50),
+            "-".to_string(),
+            "Title".to_string(),
+        ]];
+        let widths = auto_column_widths(&TABLE_HEADERS, &rows);
+        assert_eq!(widths[3], MAX_AUTO_WIDTH);
+    }
+
+    #[test]
+    fn auto_layout_cuts_long_cells_so_columns_line_up() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Short".into(), Priority::Low, None);
+        add_todo(&mut store, "Long".into(), Priority::Low, None);
+        store.todos[1].tags = vec!["a-very-long-tag-name".into(), "another-tag".into()];
+        let today = date("2026-03-01");
+        let rows: Vec<Vec<String>> = store
+            .todos
+            .iter()
+            .map(|t| table_row(t, today, &TableStyle::default()))
+            .collect();
+        let widths = auto_column_widths(&TABLE_HEADERS, &rows);
+        assert_eq!(widths[4], MAX_AUTO_WIDTH);
+
+        let lines: Vec<String> = store
+            .todos
+            .iter()
+            .zip(&rows)
+            .map(|(t, row)| format_todo_row(t, row, &widths, today, false))
+            .collect();
+        let title_at = |line: &str, title: &str| line.width() - title.width();
+        assert_eq!(title_at(&lines[0], "Short"), title_at(&lines[1], "Long"));
+        assert!(lines[1].contains("a-very-long-tag-nam… Long"));
+    }
+
+    #[test]
+    fn fixed_row_layout() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Medium, None);
+        let row = format_row(
+            &table_row(&store.todos[0], date("2026-03-01"), &TableStyle::default()),
+            &FIXED_WIDTHS,
+        );
+        assert_eq!(
+            row,
+            format!(
+                "{:<5} {:<6} {:<8} {:<20} {:<16} {}",
+                1, "[ ]", "medium", "-", "-", "Task"
+            )
+        );
+    }
+
+    #[test]
+    fn configured_markers_replace_defaults() {
+        let style = parse_markers("DONE,PENDING").unwrap();
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(&mut store, "B".into(), Priority::Low, None);
+        mark_done(&mut store, 1);
+        let today = date("2026-03-01");
+
+        assert_eq!(table_row(&store.todos[0], today, &style)[1], "DONE");
+        assert_eq!(table_row(&store.todos[1], today, &style)[1], "PENDING");
+        let widths = fixed_widths(&style);
+        assert_eq!(widths[1], 8);
+        let row = format_row(&table_row(&store.todos[0], today, &style), &widths);
+        assert!(row.starts_with("1     DONE     low"));
+    }
+
+    #[test]
+    fn wide_glyph_markers_keep_columns_aligned() {
+        let style = parse_markers("✅✅✅,-").unwrap();
+        let widths = fixed_widths(&style);
+        assert_eq!(widths[1], 7);
+
+        let mut store = empty_store();
+        add_todo(&mut store, "A".into(), Priority::Low, None);
+        add_todo(&mut store, "B".into(), Priority::Low, None);
+        mark_done(&mut store, 1);
+        let today = date("2026-03-01");
+        let lines: Vec<String> = store
+            .todos
+            .iter()
+            .map(|t| format_row(&table_row(t, today, &style), &widths))
+            .collect();
+        assert_eq!(lines[0].width(), lines[1].width());
+        assert!(lines[0].starts_with("1     ✅✅✅  low"));
+        assert_eq!(fit_cell("✅✅✅", 4), "✅…");
+    }
+
+    // -- color tests --
+
+    #[test]
+    fn priority_color_codes() {
+        assert_eq!(priority_color(&Priority::High), Some("\x1b[31m"));
+        assert_eq!(priority_color(&Priority::Medium), Some("\x1b[33m"));
+        assert_eq!(priority_color(&Priority::Low), None);
+        assert_eq!(paint("high", "\x1b[31m"), "\x1b[31mhigh\x1b[0m");
+    }
+
+    #[test]
+    fn colored_row_pads_before_painting() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::High,
+            Some("2026-02-01".into()),
+        );
+        let today = date("2026-03-01");
+        let cells = table_row(&store.todos[0], today, &TableStyle::default());
+        let row = format_todo_row(&store.todos[0], &cells, &FIXED_WIDTHS, today, true);
+        assert_eq!(
+            row,
+            format!(
+                "{:<5} {:<6} \x1b[31m{:<8}\x1b[0m \x1b[1;31m{:<20}\x1b[0m {:<16} {}",
+                1, "[ ]", "high", "2026-02-01 (OVERDUE)", "-", "Late"
+            )
+        );
+    }
+
+    #[test]
+    fn no_color_row_is_plain_text() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::High,
+            Some("2026-02-01".into()),
+        );
+        let today = date("2026-03-01");
+        let cells = table_row(&store.todos[0], today, &TableStyle::default());
+        let row = format_todo_row(&store.todos[0], &cells, &FIXED_WIDTHS, today, false);
+        assert_eq!(row, format_row(&cells, &FIXED_WIDTHS));
+        assert!(!row.contains('\x1b'));
+    }
+
+    #[test]
+    fn color_enabled_only_on_tty_without_opt_out() {
+        assert!(color_enabled(false, None, true));
+        assert!(color_enabled(false, Some(""), true));
+        assert!(!color_enabled(false, None, false));
+        assert!(!color_enabled(true, None, true));
+        assert!(!color_enabled(false, Some("1"), true));
+    }
+
+    #[test]
+    fn parse_markers_accepts_symbols_and_rejects_missing_half() {
+        let style = parse_markers("\u{2714},\u{2717}").unwrap();
+        assert_eq!(style.done_marker, "\u{2714}");
+        assert_eq!(fixed_widths(&style), FIXED_WIDTHS.to_vec());
+        assert!(parse_markers("DONE").is_err());
+        assert!(parse_markers(",TODO").is_err());
+    }
+
+    #[test]
+    fn completed_column_shows_date_or_dash() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Done".into(), Priority::Low, None);
+        add_todo(&mut store, "Pending".into(), Priority::Low, None);
+        mark_done(&mut store, 1);
+        store.todos[0].completed_at = Some("2026-03-01".into());
+        let column = completed_column();
+        assert_eq!((column.cell)(&store.todos[0]), "2026-03-01");
+        assert_eq!((column.cell)(&store.todos[1]), "-");
+    }
+
+    #[test]
+    fn completed_column_is_opt_in() {
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--filter", "done"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                show_completed_at: false,
+                ..
+            }
+        ));
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--show-completed-at"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                show_completed_at: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn assignee_column_shows_name_or_dash() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Shared".into(), Priority::Low, None);
+        add_todo(&mut store, "Mine".into(), Priority::Low, None);
+        store.todos[0].assignee = Some("sam".into());
+        let column = assignee_column();
+        assert_eq!(column.header, "Assignee");
+        assert_eq!((column.cell)(&store.todos[0]), "sam");
+        assert_eq!((column.cell)(&store.todos[1]), "-");
+        assert!(Cli::try_parse_from(["todo-cli", "list", "--wide"]).is_ok());
+    }
+
+    // -- JSON output tests --
+
+    #[test]
+    fn json_output_round_trips_filtered_todos() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Pending".into(), Priority::Low, None);
+        add_todo(
+            &mut store,
+            "Done".into(),
+            Priority::High,
+            Some("2026-03-01".into()),
+        );
+        mark_done(&mut store, 2);
+
+        let todos = filter_todos(&store, &ListFilter::Done);
+        let json = list_json(&todos, false);
+        let mut parsed: Vec<Todo> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        // Timestamps are widened to RFC 3339; everything else round-trips.
+        parsed[0].created_at = store.todos[1].created_at.clone();
+        parsed[0].completed_at = store.todos[1].completed_at.clone();
+        assert_eq!(parsed[0], store.todos[1]);
+        // Fields keep the order of the store format.
+        let position = |key: &str| json.find(&format!("\"{}\"", key)).unwrap();
+        assert!(position("id") < position("title"));
+        assert!(position("title") < position("completed"));
+        assert!(position("created_at") < position("assignee"));
+    }
+
+    #[test]
+    fn json_output_escapes_titles() {
+        let title = "Say \"hi\"\nthen 🎉";
+        let mut store = empty_store();
+        add_todo(&mut store, title.into(), Priority::Low, None);
+        let json = todos_to_json(&filter_todos(&store, &ListFilter::All));
+        assert!(json.contains(r#""Say \"hi\"\nthen 🎉""#));
+        let parsed: Vec<Todo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0].title, title);
+    }
+
+    #[test]
+    fn json_output_of_no_todos_is_empty_array() {
+        assert_eq!(todos_to_json(&[]), "[]");
+    }
+
+    #[test]
+    fn unwrapped_single_result_is_an_object() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Only".into(), Priority::Low, None);
+        let todos = filter_todos(&store, &ListFilter::All);
+        let value: serde_json::Value = serde_json::from_str(&list_json(&todos, true)).unwrap();
+        assert!(value.is_object());
+        assert_eq!(value["title"], "Only");
+    }
+
+    #[test]
+    fn single_result_stays_wrapped_in_array_by_default() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Only".into(), Priority::Low, None);
+        let todos = filter_todos(&store, &ListFilter::All);
+        let value: serde_json::Value = serde_json::from_str(&list_json(&todos, false)).unwrap();
+        assert_eq!(value.as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn list_json_emits_full_iso_timestamps_for_legacy_dates() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Legacy".into(), Priority::Low, None);
+        add_todo(&mut store, "Migrated".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-03-01".into();
+        mark_done(&mut store, 1);
+        store.todos[0].completed_at = Some("2026-03-04".into());
+        store.todos[1].created_at = "2026-03-02T09:30:00+01:00".into();
+
+        let todos = filter_todos(&store, &ListFilter::All);
+        let value: serde_json::Value = serde_json::from_str(&list_json(&todos, false)).unwrap();
+
+        let midnight = |day: &str| {
+            Local
+                .from_local_datetime(&date(day).and_time(NaiveTime::MIN))
+                .earliest()
+                .unwrap()
+                .to_rfc3339()
+        };
+        assert_eq!(value[0]["created_at"], midnight("2026-03-01"));
+        assert_eq!(value[0]["completed_at"], midnight("2026-03-04"));
+        let created = value[0]["created_at"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(created).is_ok());
+        assert_eq!(value[1]["created_at"], "2026-03-02T09:30:00+01:00");
+        assert_eq!(value[1]["completed_at"], serde_json::Value::Null);
+        // The store keeps the stored form.
+        assert_eq!(store.todos[0].created_at, "2026-03-01");
+    }
+
+    // -- import tests --
+
+    fn incoming_store() -> TodoStore {
+        let mut incoming = empty_store();
+        add_todo(&mut incoming, "Imported A".into(), Priority::High, None);
+        add_todo(
+            &mut incoming,
+            "Imported B".into(),
+            Priority::Low,
+            Some("2026-05-01".into()),
+        );
+        incoming
+    }
+
+    #[test]
+    fn merge_into_non_empty_store_assigns_fresh_ids() {
+        let mut dest = empty_store();
+        add_todo(&mut dest, "Local 1".into(), Priority::Low, None);
+        add_todo(&mut dest, "Local 2".into(), Priority::Low, None);
+
+        let count = merge_stores(&mut dest, incoming_store(), true);
+
+        assert_eq!(count, Some(2));
+        let ids: Vec<u32> = dest.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(dest.todos[2].title, "Imported A");
+        assert_eq!(dest.todos[3].due_date.as_deref(), Some("2026-05-01"));
+        assert_eq!(dest.next_id, 5);
+        assert_eq!(add_todo(&mut dest, "Next".into(), Priority::Low, None), 5);
+    }
+
+    #[test]
+    fn merge_keeping_ids_still_avoids_collisions() {
+        let mut dest = empty_store();
+        add_todo(&mut dest, "Local 1".into(), Priority::Low, None);
+        let mut incoming = incoming_store();
+        incoming.todos[1].id = 10;
+
+        merge_stores(&mut dest, incoming, false).unwrap();
+
+        let ids: Vec<u32> = dest.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 10]);
+        assert_eq!(dest.next_id, 11);
+    }
+
+    #[test]
+    fn merge_reassigns_max_id_and_fails_when_ids_run_out() {
+        let mut dest = empty_store();
+        add_todo(&mut dest, "Local 1".into(), Priority::Low, None);
+        let mut incoming = incoming_store();
+        incoming.todos[0].id = u32::MAX;
+
+        assert_eq!(merge_stores(&mut dest, incoming, false), Some(2));
+        let ids: Vec<u32> = dest.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(dest.next_id, 4);
+
+        dest.next_id = u32::MAX;
+        assert_eq!(merge_stores(&mut dest, incoming_store(), true), None);
+    }
+
+    #[test]
+    fn import_reads_exported_array_and_whole_store() {
+        let store = incoming_store();
+        let todos: Vec<&Todo> = store.todos.iter().collect();
+        assert_eq!(
+            parse_import(&todos_to_json(&todos)).unwrap().todos,
+            store.todos
+        );
+        let whole = serde_json::to_string(&store).unwrap();
+        assert_eq!(parse_import(&whole).unwrap().todos, store.todos);
+        assert!(parse_import("{oops").is_err());
+    }
+
+    #[test]
+    fn full_export_round_trips_through_replace() {
+        let mut store = incoming_store();
+        mark_done(&mut store, 1);
+        store.todos[1].comments = vec!["2026-03-01 09:00 started".into()];
+        store.list_cursor = 1;
+        store.completion_streak = 3;
+        store.last_completion_date = Some("2026-03-01".into());
+        store.next_id = 7;
+
+        let restored = restore_store(parse_import(&store_to_json(&store)).unwrap()).unwrap();
+
+        assert_eq!(restored, store);
+    }
+
+    #[test]
+    fn restore_store_fixes_next_id_and_rejects_bad_ids() {
+        let mut store = incoming_store();
+        store.next_id = 0;
+        assert_eq!(restore_store(store).unwrap().next_id, 3);
+
+        let mut store = incoming_store();
+        store.todos[1].id = 1;
+        assert!(restore_store(store).is_err());
+
+        let mut store = incoming_store();
+        store.todos[1].id = u32::MAX;
+        assert!(restore_store(store).is_err());
+    }
+
+    // -- CSV export tests --
+
+    #[test]
+    fn csv_has_header_and_every_column() {
+        let mut store = empty_store();
+        add_todo(
+            &mut store,
+            "Plain".into(),
+            Priority::High,
+            Some("2026-03-01".into()),
+        );
+        add_todo(&mut store, "Undated".into(), Priority::Low, None);
+        store.todos[0].created_at = "2026-02-01".into();
+        store.todos[1].created_at = "2026-02-02".into();
+        mark_done(&mut store, 2);
+        assert_eq!(
+            to_csv(&store),
+            "id,title,completed,priority,due_date,created_at\n\
+             1,Plain,false,high,2026-03-01,2026-02-01\n\
+             2,Undated,true,low,,2026-02-02\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_commas_quotes_and_newlines() {
+        assert_eq!(csv_field("Milk, eggs"), "\"Milk, eggs\"");
+        assert_eq!(csv_field("Say \"hi\""), "\"Say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("plain"), "plain");
+
         let mut store = empty_store();
         add_todo(
             &mut store,
-            "Buy milk".into(),
-            Priority::High,
-            Some("2026-03-01".into()),
+            "Buy milk, \"fresh\"".into(),
+            Priority::Medium,
+            None,
         );
-        assert_eq!(store.todos.len(), 1);
-        let todo = &store.todos[0];
-        assert_eq!(todo.title, "Buy milk");
-        assert_eq!(todo.priority, Priority::High);
-        assert_eq!(todo.due_date.as_deref(), Some("2026-03-01"));
-        assert!(!todo.completed);
+        let row = to_csv(&store).lines().nth(1).unwrap().to_string();
+        assert!(row.starts_with("1,\"Buy milk, \"\"fresh\"\"\",false,medium,,"));
     }
 
+    // -- priority_status_matrix tests --
+
     #[test]
-    fn add_todo_defaults_to_not_completed() {
+    fn priority_status_matrix_counts_each_cell() {
         let mut store = empty_store();
-        add_todo(&mut store, "Task".into(), Priority::Medium, None);
-        assert!(!store.todos[0].completed);
+        add_todo(&mut store, "H1".into(), Priority::High, None);
+        add_todo(&mut store, "H2".into(), Priority::High, None);
+        add_todo(&mut store, "H3".into(), Priority::High, None);
+        add_todo(&mut store, "M1".into(), Priority::Medium, None);
+        add_todo(&mut store, "L1".into(), Priority::Low, None);
+        add_todo(&mut store, "L2".into(), Priority::Low, None);
+        mark_done(&mut store, 1);
+        mark_done(&mut store, 5);
+        mark_done(&mut store, 6);
+        let todos = filter_todos(&store, &ListFilter::All);
+        assert_eq!(priority_status_matrix(&todos), [[2, 1], [1, 0], [0, 2]]);
     }
 
-    // -- mark_done tests --
+    #[test]
+    fn priority_status_matrix_counts_only_given_todos() {
+        let store = tagged_store();
+        let mut todos = filter_todos(&store, &ListFilter::All);
+        todos.retain(|t| has_tag(t, "work"));
+        assert_eq!(priority_status_matrix(&todos), [[0, 1], [0, 0], [1, 0]]);
+    }
 
     #[test]
-    fn mark_done_existing_todo() {
-        let mut store = empty_store();
-        add_todo(&mut store, "Task".into(), Priority::Medium, None);
-        assert!(mark_done(&mut store, 1));
-        assert!(store.todos[0].completed);
+    fn priority_status_matrix_empty_store() {
+        assert_eq!(priority_status_matrix(&[]), [[0; 2]; 3]);
     }
 
+    // -- stats tests --
+
     #[test]
-    fn mark_done_nonexistent_returns_false() {
+    fn compute_stats_counts_every_field() {
         let mut store = empty_store();
-        assert!(!mark_done(&mut store, 99));
+        add_todo(
+            &mut store,
+            "Late".into(),
+            Priority::High,
+            Some("2020-01-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "Later".into(),
+            Priority::High,
+            Some("2999-01-01".into()),
+        );
+        add_todo(
+            &mut store,
+            "Late but done".into(),
+            Priority::Medium,
+            Some("2020-01-01".into()),
+        );
+        add_todo(&mut store, "Plain".into(), Priority::Low, None);
+        add_todo(&mut store, "Done".into(), Priority::Low, None);
+        mark_done(&mut store, 3);
+        mark_done(&mut store, 5);
+        assert_eq!(
+            compute_stats(&store),
+            Stats {
+                total: 5,
+                completed: 2,
+                pending: 3,
+                overdue: 1,
+                high: 2,
+                medium: 1,
+                low: 2,
+            }
+        );
     }
 
     #[test]
-    fn mark_done_idempotent() {
+    fn compute_stats_empty_store_is_all_zero() {
+        assert_eq!(compute_stats(&empty_store()), Stats::default());
+    }
+
+    // -- persistence tests --
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("round-trip");
         let mut store = empty_store();
-        add_todo(&mut store, "Task".into(), Priority::Low, None);
-        assert!(mark_done(&mut store, 1));
-        assert!(mark_done(&mut store, 1));
-        assert!(store.todos[0].completed);
+        add_todo(
+            &mut store,
+            "Persist me".into(),
+            Priority::High,
+            Some("2026-12-31".into()),
+        );
+        mark_done(&mut store, 1);
+
+        save_store(&store, &path, false).unwrap();
+        let loaded = load_store(&path).unwrap();
+
+        assert_eq!(loaded.next_id, 2);
+        assert_eq!(loaded.todos.len(), 1);
+        assert_eq!(loaded.todos[0].title, "Persist me");
+        assert_eq!(loaded.todos[0].priority, Priority::High);
+        assert!(loaded.todos[0].completed);
+        assert_eq!(loaded.todos[0].due_date.as_deref(), Some("2026-12-31"));
+
+        fs::remove_file(&path).ok();
     }
 
-    // -- remove_todo tests --
+    #[test]
+    fn titles_needing_escapes_round_trip() {
+        let title = "Say \"hi\" \\ to\nBob\t\u{1} 🎉";
+        let path = temp_path("escapes");
+        let mut store = empty_store();
+        add_todo(&mut store, title.into(), Priority::Medium, None);
+
+        save_store(&store, &path, false).unwrap();
+        let loaded = load_store(&path).unwrap();
+        assert_eq!(loaded.todos[0].title, title);
+
+        save_store(&store, &path, true).unwrap();
+        let loaded = load_store(&path).unwrap();
+        assert_eq!(loaded.todos[0].title, title);
+
+        fs::remove_file(&path).ok();
+        fs::remove_file(backup_store_path(&path)).ok();
+    }
 
     #[test]
-    fn remove_existing_todo() {
+    fn compact_save_round_trips() {
+        let path = temp_path("compact");
         let mut store = empty_store();
-        add_todo(&mut store, "Task".into(), Priority::Medium, None);
-        assert!(remove_todo(&mut store, 1));
-        assert!(store.todos.is_empty());
+        add_todo(&mut store, "Compact".into(), Priority::Low, None);
+        add_todo(&mut store, "Store".into(), Priority::High, None);
+
+        save_store(&store, &path, true).unwrap();
+        let data = fs::read_to_string(&path).unwrap();
+        let loaded = load_store(&path).unwrap();
+
+        assert!(!data.contains('\n'));
+        assert_eq!(loaded, store);
+
+        fs::remove_file(&path).ok();
     }
 
     #[test]
-    fn remove_nonexistent_returns_false() {
+    fn save_store_replaces_existing_file_without_leftover_temp() {
+        let path = temp_path("atomic-save");
         let mut store = empty_store();
-        assert!(!remove_todo(&mut store, 99));
+        add_todo(&mut store, "Old".into(), Priority::Low, None);
+        save_store(&store, &path, false).unwrap();
+
+        for i in 0..50 {
+            add_todo(&mut store, format!("New {}", i), Priority::High, None);
+        }
+        save_store(&store, &path, false).unwrap();
+
+        assert_eq!(load_store(&path).unwrap(), store);
+        assert!(!temp_store_path(&path).exists());
+        fs::remove_file(&path).ok();
+        fs::remove_file(backup_store_path(&path)).ok();
     }
 
     #[test]
-    fn remove_only_target_todo() {
+    fn save_store_cleans_up_temp_when_rename_fails() {
+        // Renaming a file over a non-empty directory fails.
+        let path = temp_path("atomic-save-dir");
+        fs::create_dir_all(path.join("occupied")).unwrap();
+        assert!(save_store(&empty_store(), &path, false).is_err());
+        assert!(!temp_store_path(&path).exists());
+        fs::remove_dir_all(&path).ok();
+    }
+
+    #[test]
+    fn undo_restores_removed_todo_with_original_fields() {
+        let path = temp_path("undo");
+        let backup = backup_store_path(&path);
+        fs::remove_file(&backup).ok();
         let mut store = empty_store();
         add_todo(&mut store, "Keep".into(), Priority::Low, None);
-        add_todo(&mut store, "Remove".into(), Priority::High, None);
-        assert!(remove_todo(&mut store, 2));
-        assert_eq!(store.todos.len(), 1);
-        assert_eq!(store.todos[0].title, "Keep");
-    }
+        add_todo(
+            &mut store,
+            "Oops".into(),
+            Priority::High,
+            Some("2026-04-01".into()),
+        );
+        store.todos[1].tags = vec!["work".into()];
+        save_store(&store, &path, false).unwrap();
+        let original = load_store(&path).unwrap();
 
-    // -- filter_todos tests --
+        remove_todo(&mut store, 2);
+        save_store(&store, &path, false).unwrap();
+        assert!(restore_backup(&path, false, false).unwrap());
+
+        let restored = load_store(&path).unwrap();
+        assert_eq!(restored, original);
+        assert_eq!(restored.todos[1].id, 2);
+        assert_eq!(restored.todos[1].title, "Oops");
+
+        // A second undo swaps back to the state after the removal.
+        assert!(restore_backup(&path, false, false).unwrap());
+        assert_eq!(load_store(&path).unwrap().todos.len(), 1);
+
+        fs::remove_file(&path).ok();
+        fs::remove_file(&backup).ok();
+    }
 
     #[test]
-    fn filter_pending_excludes_done() {
+    fn save_without_backup_keeps_previous_backup() {
+        let path = temp_path("no-backup");
+        let backup = backup_store_path(&path);
         let mut store = empty_store();
-        add_todo(&mut store, "Pending".into(), Priority::Low, None);
-        add_todo(&mut store, "Done".into(), Priority::Low, None);
-        mark_done(&mut store, 2);
-        let result = filter_todos(&store, &ListFilter::Pending);
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].title, "Pending");
+        add_todo(&mut store, "First".into(), Priority::Low, None);
+        save_store(&store, &path, false).unwrap();
+        add_todo(&mut store, "Second".into(), Priority::Low, None);
+        save_store(&store, &path, false).unwrap();
+        let before = load_store(&backup).unwrap();
+
+        escalate_stale(&mut store, date("2099-01-01"), 30);
+        persist_without_backup(&store, &path, false, false).unwrap();
+
+        assert_eq!(load_store(&backup).unwrap(), before);
+        assert_eq!(load_store(&path).unwrap(), store);
+        fs::remove_file(&path).ok();
+        fs::remove_file(&backup).ok();
     }
 
     #[test]
-    fn filter_done_excludes_pending() {
+    fn paging_does_not_use_up_undo() {
+        let path = temp_path("undo-paging");
+        let backup = backup_store_path(&path);
+        fs::remove_file(&backup).ok();
         let mut store = empty_store();
-        add_todo(&mut store, "Pending".into(), Priority::Low, None);
-        add_todo(&mut store, "Done".into(), Priority::Low, None);
-        mark_done(&mut store, 2);
-        let result = filter_todos(&store, &ListFilter::Done);
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].title, "Done");
+        add_todo(&mut store, "Before".into(), Priority::Low, None);
+        save_store(&store, &path, false).unwrap();
+        add_todos(&mut store, &["a".into(), "b".into()], Priority::Low, None);
+        save_store(&store, &path, false).unwrap();
+
+        store.list_cursor = 2;
+        persist_without_backup(&store, &path, false, false).unwrap();
+        assert!(restore_backup(&path, false, false).unwrap());
+
+        let titles: Vec<String> = load_store(&path)
+            .unwrap()
+            .todos
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(titles, vec!["Before"]);
+        fs::remove_file(&path).ok();
+        fs::remove_file(&backup).ok();
     }
 
     #[test]
-    fn filter_all_returns_everything() {
+    fn save_list_cursor_leaves_in_memory_escalations_unsaved() {
+        let path = temp_path("cursor-escalation");
         let mut store = empty_store();
-        add_todo(&mut store, "A".into(), Priority::Low, None);
-        add_todo(&mut store, "B".into(), Priority::High, None);
-        mark_done(&mut store, 2);
-        let result = filter_todos(&store, &ListFilter::All);
-        assert_eq!(result.len(), 2);
+        add_todo(&mut store, "Stale".into(), Priority::Low, None);
+        store.todos[0].created_at = "2020-01-01".into();
+        save_store(&store, &path, false).unwrap();
+
+        assert_eq!(escalate_stale(&mut store, date("2026-03-01"), 30), 1);
+        assert!(save_list_cursor(&path, 4, false, false).unwrap());
+
+        let saved = load_store(&path).unwrap();
+        assert_eq!(saved.list_cursor, 4);
+        assert_eq!(saved.todos[0].priority, Priority::Low);
+        assert!(!saved.todos[0].escalated);
+        fs::remove_file(&path).ok();
+        fs::remove_file(backup_store_path(&path)).ok();
     }
 
-    // -- persistence tests --
+    #[test]
+    fn undo_without_backup_does_nothing() {
+        let path = temp_path("undo-none");
+        fs::remove_file(backup_store_path(&path)).ok();
+        assert!(!restore_backup(&path, false, false).unwrap());
+        assert!(!path.exists());
+    }
 
     #[test]
-    fn save_and_load_round_trip() {
-        let path = temp_path();
+    fn read_store_file_rejects_invalid_utf8() {
+        let path = temp_path("invalid-utf8");
+        fs::write(&path, [0x7b, 0xff, 0xfe, 0x7d]).unwrap();
+        let err = read_store_file(&path).unwrap_err();
+        assert_eq!(err.to_string(), "store file is not valid UTF-8");
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn load_store_reports_unreadable_path() {
+        // A directory exists but cannot be read as a file.
+        let path = std::env::temp_dir();
+        let err = load_store(&path).unwrap_err();
+        assert!(matches!(err, StoreError::Read(..)));
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn load_store_reports_malformed_json_instead_of_resetting() {
+        let path = temp_path("malformed");
+        fs::write(&path, "{\"next_id\": 3, \"todos\": [").unwrap();
+        let err = load_store(&path).unwrap_err();
+        assert!(matches!(err, StoreError::Parse(..)));
+        let message = err.to_string();
+        assert!(message.contains(&path.display().to_string()));
+        assert!(message.contains("not valid JSON"));
+        fs::remove_file(&path).ok();
+    }
+
+    // -- dry run tests --
+
+    #[test]
+    fn dry_run_add_plans_without_persisting() {
+        let path = temp_path("dry-run-add");
+        fs::remove_file(&path).ok();
+        let mut store = load_store(&path).unwrap();
+        add_todo(&mut store, "Buy milk".into(), Priority::High, None);
+
+        assert!(!persist(&store, &path, false, true).unwrap());
+
+        assert!(!path.exists());
+        assert_eq!(
+            plan_changes(&load_store(&path).unwrap(), &store),
+            vec!["+ add #1 \"Buy milk\"", "~ set next_id: 1 -> 2"]
+        );
+    }
+
+    #[test]
+    fn persist_reports_saving_only_outside_dry_run() {
+        let path = temp_path("dry-run-saved");
+        fs::remove_file(&path).ok();
+        let mut store = load_store(&path).unwrap();
+        add_todo(&mut store, "Buy milk".into(), Priority::High, None);
+
+        assert!(!persist(&store, &path, false, true).unwrap());
+        assert!(!restore_backup(&path, false, true).unwrap());
+        assert!(persist(&store, &path, false, false).unwrap());
+        assert!(path.exists());
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn plan_changes_reports_updates_and_removals() {
+        let mut before = empty_store();
+        add_todo(&mut before, "Keep".into(), Priority::Low, None);
+        add_todo(&mut before, "Drop".into(), Priority::Low, None);
+        let mut after = empty_store();
+        add_todo(&mut after, "Keep".into(), Priority::Low, None);
+        after.next_id = before.next_id;
+        after.todos[0].created_at = before.todos[0].created_at.clone();
+        mark_done(&mut after, 1);
+        after.todos[0].completed_at = Some("2026-03-01".into());
+
+        assert_eq!(
+            plan_changes(&before, &after),
+            vec![
+                "~ update #1 \"Keep\": completed: false -> true, completed_at: null -> \"2026-03-01\"",
+                "- remove #2 \"Drop\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_changes_reports_store_fields() {
+        let path = temp_path("dry-run-cursor");
         let mut store = empty_store();
-        add_todo(
-            &mut store,
-            "Persist me".into(),
-            Priority::High,
-            Some("2026-12-31".into()),
+        store.list_cursor = 10;
+        save_store(&store, &path, false).unwrap();
+
+        store.list_cursor = 0;
+        persist_without_backup(&store, &path, false, true).unwrap();
+
+        assert_eq!(load_store(&path).unwrap().list_cursor, 10);
+        assert_eq!(
+            plan_changes(&load_store(&path).unwrap(), &store),
+            vec!["~ set list_cursor: 10 -> 0"]
         );
-        mark_done(&mut store, 1);
+        fs::remove_file(&path).ok();
+    }
 
-        save_store(&store, &path);
-        let loaded = load_store(&path);
+    #[test]
+    fn plan_changes_empty_when_unchanged() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Same".into(), Priority::Low, None);
+        assert!(plan_changes(&store, &store).is_empty());
+    }
 
-        assert_eq!(loaded.next_id, 2);
-        assert_eq!(loaded.todos.len(), 1);
-        assert_eq!(loaded.todos[0].title, "Persist me");
-        assert_eq!(loaded.todos[0].priority, Priority::High);
-        assert!(loaded.todos[0].completed);
-        assert_eq!(loaded.todos[0].due_date.as_deref(), Some("2026-12-31"));
+    #[test]
+    fn store_errors_map_to_exit_codes() {
+        let path = PathBuf::from("/tmp/todo-cli-exit-code.json");
+        let io_error = || io::Error::other("boom");
+        let parse_error = serde_json::from_str::<TodoStore>("{").unwrap_err();
+        assert_eq!(StoreError::Missing(path.clone()).exit_code(), 4);
+        assert_eq!(StoreError::NotUtf8.exit_code(), 4);
+        assert_eq!(StoreError::NoHome.exit_code(), 1);
+        assert_eq!(StoreError::Read(path.clone(), io_error()).exit_code(), 1);
+        assert_eq!(StoreError::Parse(path.clone(), parse_error).exit_code(), 1);
+        assert_eq!(StoreError::Write(path, io_error()).exit_code(), 1);
+        assert_eq!(StoreError::Input("bad".into()).exit_code(), 1);
+        assert_eq!(StoreError::Prompt(io_error()).exit_code(), 1);
+    }
+
+    #[test]
+    fn not_found_lists_every_missing_id() {
+        assert_eq!(not_found(&[3]).to_string(), "Todo #3 not found.");
+        assert_eq!(not_found(&[3, 5]).to_string(), "Todo #3, #5 not found.");
+    }
+
+    #[test]
+    fn require_store_rejects_missing_file() {
+        let path = PathBuf::from("/tmp/todo-cli-does-not-exist.json");
+        let err = require_store(&path).unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn require_store_accepts_existing_file() {
+        let path = temp_path("require-store");
+        save_store(&empty_store(), &path, false).unwrap();
+        assert!(require_store(&path).is_ok());
+        fs::remove_file(&path).ok();
+    }
 
+    #[test]
+    fn open_store_with_no_create_rejects_only_missing_file() {
+        let path = temp_path("open-store");
+        assert!(open_store(&path, true).is_err());
+        assert_eq!(open_store(&path, false).unwrap().next_id, 1);
+        save_store(&empty_store(), &path, false).unwrap();
+        assert!(open_store(&path, true).is_ok());
         fs::remove_file(&path).ok();
     }
 
     #[test]
     fn load_nonexistent_returns_empty_store() {
         let path = PathBuf::from("/tmp/todo-cli-does-not-exist.json");
-        let store = load_store(&path);
+        let store = load_store(&path).unwrap();
         assert_eq!(store.next_id, 1);
         assert!(store.todos.is_empty());
     }
 
+    // -- store_info tests --
+
+    #[test]
+    fn store_info_reports_path_and_count() {
+        let path = temp_path("where");
+        let mut store = empty_store();
+        add_todos(
+            &mut store,
+            &["A".into(), "B".into(), "C".into()],
+            Priority::Low,
+            None,
+        );
+        save_store(&store, &path, false).unwrap();
+
+        let json = serde_json::to_string(&store_info(&path).unwrap()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["path"], path.display().to_string());
+        assert_eq!(value["exists"], true);
+        assert_eq!(value["count"], 3);
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn store_info_for_missing_file() {
+        let path = PathBuf::from("/tmp/todo-cli-does-not-exist.json");
+        let info = store_info(&path).unwrap();
+        assert!(!info.exists);
+        assert_eq!(info.count, 0);
+    }
+
+    // -- schema tests --
+
+    #[test]
+    fn schema_is_valid_json_requiring_id_and_title() {
+        let text = serde_json::to_string_pretty(&store_schema()).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let required = schema["$defs"]["Todo"]["required"].as_array().unwrap();
+        assert!(required.contains(&"id".into()));
+        assert!(required.contains(&"title".into()));
+    }
+
+    #[test]
+    fn schema_lists_every_field() {
+        let mut store = empty_store();
+        add_todo(&mut store, "Task".into(), Priority::Low, None);
+        let todo = serde_json::to_value(&store.todos[0]).unwrap();
+        let schema = store_schema();
+        let properties = schema["$defs"]["Todo"]["properties"].as_object().unwrap();
+        for field in todo.as_object().unwrap().keys() {
+            assert!(properties.contains_key(field), "schema missing {}", field);
+        }
+        let store_fields = serde_json::to_value(&store).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for field in store_fields.as_object().unwrap().keys() {
+            assert!(properties.contains_key(field), "schema missing {}", field);
+        }
+    }
+
+    // -- store_path tests --
+
+    #[test]
+    fn store_path_honors_env_override() {
+        let home = PathBuf::from("/nonexistent-todo-cli-home");
+        let cwd = home.join("work");
+        let custom = temp_path("env-store");
+        let custom = custom.to_str().unwrap();
+        let resolve = |global, env| resolve_store_path(global, env, Some(&cwd), Some(&home));
+
+        assert_eq!(resolve(false, Some(custom)).unwrap(), PathBuf::from(custom));
+        assert_eq!(
+            resolve(true, Some(custom)).unwrap(),
+            home.join(STORE_FILE_NAME)
+        );
+        assert_eq!(
+            resolve(false, Some("")).unwrap(),
+            home.join(STORE_FILE_NAME)
+        );
+        assert_eq!(resolve(false, None).unwrap(), home.join(STORE_FILE_NAME));
+        assert!(matches!(
+            resolve_store_path(false, None, Some(&cwd), None),
+            Err(StoreError::NoHome)
+        ));
+    }
+
+    #[test]
+    fn find_store_path_walks_upward() {
+        let mut root = std::env::temp_dir();
+        root.push(format!("todo-cli-test-tree-{}", std::process::id()));
+        let project = root.join("project");
+        let nested = project.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        let store = project.join(STORE_FILE_NAME);
+        fs::write(&store, "{}").unwrap();
+
+        // Stop at the temp root so stores above it can't leak in.
+        let stop = Some(root.as_path());
+        assert_eq!(find_store_path(&nested, stop), Some(store.clone()));
+        assert_eq!(find_store_path(&project, stop), Some(store));
+        assert_eq!(find_store_path(&root, stop), None);
+
+        // Outside `stop` only the start directory itself is searched.
+        let elsewhere = root.join("home");
+        fs::create_dir_all(&elsewhere).unwrap();
+        let outside = Some(elsewhere.as_path());
+        assert_eq!(find_store_path(&nested, outside), None);
+        assert_eq!(
+            find_store_path(&project, outside),
+            Some(project.join(STORE_FILE_NAME))
+        );
+        assert_eq!(find_store_path(&nested, None), None);
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn global_flag_selects_home_store() {
+        let mut home = std::env::temp_dir();
+        home.push(format!("todo-cli-test-home-{}", std::process::id()));
+        let project = home.join("project");
+        let cwd = project.join("src");
+        fs::create_dir_all(&cwd).unwrap();
+        let project_store = project.join(STORE_FILE_NAME);
+        fs::write(&project_store, "{}").unwrap();
+        let resolve = |global, env: Option<&str>| {
+            resolve_store_path(global, env, Some(&cwd), Some(&home)).unwrap()
+        };
+
+        let cli = Cli::try_parse_from(["todo-cli", "list", "--global"]).unwrap();
+        assert!(cli.global);
+        assert_eq!(resolve(false, None), project_store);
+        assert_eq!(resolve(false, Some("")), project_store);
+        assert_eq!(resolve(cli.global, None), home.join(STORE_FILE_NAME));
+        assert_eq!(
+            resolve(false, Some("/tmp/x.json")),
+            PathBuf::from("/tmp/x.json")
+        );
+
+        fs::remove_dir_all(&home).ok();
+    }
+
     // -- Priority display tests --
 
     #[test]
@@ -390,4 +5800,9 @@ mod tests {
         assert_eq!(Priority::Medium.to_string(), "medium");
         assert_eq!(Priority::Low.to_string(), "low");
     }
+
+    #[test]
+    fn priority_display_honors_width() {
+        assert_eq!(format!("{:<8}|", Priority::Low), "low     |");
+    }
 }